@@ -13,7 +13,7 @@ struct ExampleStruct {
 fn writer() {
     let mut w = Writer::from_path("basic-serde-example.bin").unwrap();
     let a = ExampleStruct { _num: 30202, _string: "Hello Stenway!".to_string(), _option: None };
-    let b = ExampleStruct { _num: -30202, _string: "Hello Stenway!".to_string(), _option: Some(3.14) };
+    let b = ExampleStruct { _num: -30202, _string: "Hello Stenway!".to_string(), _option: Some(2.5) };
 
     w.serialize(&a).unwrap();
     w.serialize(&b).unwrap();
@@ -1,5 +1,3 @@
-use std::fs::File;
-
 use rsv_core::reader::Reader;
 use rsv_core::writer::Writer;
 use serde::{Deserialize, Serialize};
@@ -13,34 +11,27 @@ struct ExampleStruct {
 }
 
 // ~~~~~~~~~~~
-// Add the zstd crate in order to run this example!!
+// Run with `--features zstd` to enable zstd support!!
 // ~~~~~~~~~~~
 
 fn writer() {
-    // We are able to write RSV objects into anything that implements the write trait.
-    // this includes things like files, stdio, and compressors.
-    let f = File::create("zstd-example.bin").unwrap();
-    let w = zstd::stream::write::Encoder::new(f, 0).unwrap();
-    let w = w.auto_finish();
-
-    // Since the zstd encoder is already internally buffered, we can create
-    // our RSV encoder with no buffering.
-    let mut w = Writer::from_writer_unbuffered(w);
+    // The `.zst` extension makes the writer compress with zstd.
+    let mut w = Writer::from_path("zstd-example.rsv.zst").unwrap();
 
     let a = ExampleStruct { _num: 30202, _string: "Hello Stenway!".to_string(), _option: None };
-    let b = ExampleStruct { _num: -30202, _string: "Hello Stenway!".to_string(), _option: Some(3.14) };
+    let b = ExampleStruct { _num: -30202, _string: "Hello Stenway!".to_string(), _option: Some(2.5) };
 
     w.serialize(&a).unwrap();
     w.serialize(&b).unwrap();
+
+    // Finishing ends the zstd frame and reports any error, which dropping
+    // the writer would discard.
+    w.finish().unwrap();
 }
 
 fn reader() {
-    // We are able to read RSV objects from anything that implements the read trait.
-    // this includes things like files, stdio, and compressors.
-    let f = File::open("zstd-example.bin").unwrap();
-    let r = zstd::stream::read::Decoder::new(f).unwrap();
-
-    let mut r = Reader::from_reader(r);
+    // Compression is detected from the first bytes of the file.
+    let mut r = Reader::from_path("zstd-example.rsv.zst").unwrap();
     let mut r = r.deserialize::<ExampleStruct>();
 
     println!("{:#?}", r.next());
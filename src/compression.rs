@@ -0,0 +1,186 @@
+//! Transparent compression for the path based readers and writers.
+//!
+//! Each format is enabled by a feature of the same name: `zstd`, `gzip`, `xz`
+//! and `bzip2`. Files in a format whose feature is disabled are read and
+//! written as is, like in builds without compression support.
+//!
+//! The bzip2 magic bytes are plain ASCII, so a bzip2 file is only recognized
+//! when its name also ends in `.bz2`.
+
+use std::{fs::File, io::{self, BufReader, BufWriter, Read, Write}, path::Path};
+
+/// A compression format, detected from the magic bytes of a file when reading
+/// and from its extension when writing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    /// `.zst` or `.zstd`
+    Zstd,
+    /// `.gz`
+    Gzip,
+    /// `.xz`
+    Xz,
+    /// `.bz2`
+    Bzip2,
+}
+
+impl Compression {
+    /// Picks a format from the last extension of `path`, e.g. `data.rsv.zst`.
+    pub fn from_extension(path: &Path) -> Compression {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("zst" | "zstd") => Compression::Zstd,
+            Some("gz") => Compression::Gzip,
+            Some("xz") => Compression::Xz,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+
+    /// Detects a format from the first bytes of a file. At least 10 bytes are
+    /// needed to tell bzip2 apart from text.
+    pub fn detect(head: &[u8]) -> Compression {
+        match head {
+            [0x28, 0xB5, 0x2F, 0xFD, ..] => Compression::Zstd,
+            [0x1F, 0x8B, ..] => Compression::Gzip,
+            [0xFD, b'7', b'z', b'X', b'Z', 0x00, ..] => Compression::Xz,
+            // The level is followed by the magic of either a block or the end of the stream
+            [b'B', b'Z', b'h', b'1'..=b'9', rest @ ..]
+                if rest.starts_with(&[0x31, 0x41, 0x59, 0x26, 0x53, 0x59])
+                || rest.starts_with(&[0x17, 0x72, 0x45, 0x38, 0x50, 0x90]) => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+
+    /// Only selects the format if support for it was compiled in, falling
+    /// back to plain files otherwise.
+    pub(crate) fn enabled(self) -> Compression {
+        match self {
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => self,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => self,
+            #[cfg(feature = "xz")]
+            Compression::Xz => self,
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => self,
+            _ => Compression::None,
+        }
+    }
+
+    fn unsupported(self) -> io::Error {
+        let feature = match self {
+            Compression::None => "",
+            Compression::Zstd => "zstd",
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bzip2",
+        };
+        io::Error::new(io::ErrorKind::Unsupported, format!("{:?} files need the `{}` feature", self, feature))
+    }
+
+    pub(crate) fn decoder(self, file: File) -> io::Result<Box<dyn Read + Send>> {
+        let file = BufReader::new(file);
+        Ok(match self {
+            Compression::None => Box::new(file),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(file)),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(file)),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(file)),
+            #[allow(unreachable_patterns)]
+            other => return Err(other.unsupported()),
+        })
+    }
+}
+
+/// A buffered file, compressed or not.
+pub(crate) enum Encoder {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "xz")]
+    Xz(xz2::write::XzEncoder<BufWriter<File>>),
+    #[cfg(feature = "bzip2")]
+    Bzip2(bzip2::write::BzEncoder<BufWriter<File>>),
+}
+
+macro_rules! dispatch {
+    ($enc:expr, $w:ident => $body:expr) => {
+        match $enc {
+            Encoder::Plain($w) => $body,
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd($w) => $body,
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip($w) => $body,
+            #[cfg(feature = "xz")]
+            Encoder::Xz($w) => $body,
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2($w) => $body,
+        }
+    };
+}
+
+impl Encoder {
+    pub(crate) fn new(file: File, compression: Compression) -> io::Result<Encoder> {
+        let file = BufWriter::new(file);
+        Ok(match compression {
+            Compression::None => Encoder::Plain(file),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(file, 0)?),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(file, flate2::Compression::default())),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Encoder::Xz(xz2::write::XzEncoder::new(file, 6)),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Encoder::Bzip2(bzip2::write::BzEncoder::new(file, bzip2::Compression::default())),
+            #[allow(unreachable_patterns)]
+            other => return Err(other.unsupported()),
+        })
+    }
+
+    pub(crate) fn get_ref(&self) -> &File {
+        match self {
+            Encoder::Plain(w) => w.get_ref(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(w) => w.get_ref().get_ref(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(w) => w.get_ref().get_ref(),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(w) => w.get_ref().get_ref(),
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(w) => w.get_ref().get_ref(),
+        }
+    }
+
+    /// Ends the compressed stream and flushes everything to the file. Writing
+    /// afterwards is not allowed, but finishing again does nothing.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(w) => w.do_finish().and_then(|_| w.get_mut().flush()),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(w) => w.try_finish().and_then(|_| w.get_mut().flush()),
+            #[cfg(feature = "xz")]
+            Encoder::Xz(w) => w.try_finish().and_then(|_| w.get_mut().flush()),
+            #[cfg(feature = "bzip2")]
+            Encoder::Bzip2(w) => w.try_finish().and_then(|_| w.get_mut().flush()),
+        }
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        dispatch!(self, w => w.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        dispatch!(self, w => w.flush())
+    }
+}
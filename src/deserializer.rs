@@ -1,70 +1,226 @@
-use std::str::from_utf8;
+use std::any::type_name;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::Read;
+use std::str::{from_utf8, FromStr};
 
-use serde::{de::SeqAccess, Deserializer};
+use serde::{de::{DeserializeOwned, SeqAccess, Visitor}, Deserialize, Deserializer};
 
+use crate::diagnostics::{Diagnostic, DiagnosticKind, EXCERPT_LEN};
 use crate::error::{Error, ErrorKind};
 use crate::utils::{NULL_BYTE, VALUE_TERM_BYTE, ROW_TERM_BYTE};
+use crate::encoding::ByteEncoding;
+use crate::policy::{ExtraCells, MissingCells, ParsePolicy, Truncation, Utf8Policy};
+use crate::value::ValueInference;
+
+/// Reader settings that affect how individual values are decoded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DeOptions {
+    pub(crate) inference: ValueInference,
+    pub(crate) bytes: ByteEncoding,
+    pub(crate) policy: ParsePolicy,
+    pub(crate) missing: MissingCells,
+    pub(crate) extra: ExtraCells,
+    pub(crate) utf8: Utf8Policy,
+}
 
 pub struct DeRecord<'de> {
     buf: &'de [u8],
+    opts: DeOptions,
+    // Set once the record itself has been entered, after which
+    // `deserialize_any` operates on single values instead of the whole row.
+    in_row: bool,
+    // Set while a field that is itself a sequence consumes the rest of the row
+    nested: bool,
+    // Index of the next top level field, and the number of fields of the
+    // struct or tuple being deserialized, if known
+    field: usize,
+    expected: Option<usize>,
+    // Set once the row ran out of values before `expected` was reached
+    short: bool,
+    // Index of the next value, counting every value in the row, and of the
+    // value being read
+    cell: usize,
+    current: usize,
+    row_len: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'de> DeRecord<'de> {
-    pub(crate) fn from_ref(buf: &'de [u8]) -> Self {
-        DeRecord { buf }
+    pub(crate) fn from_ref(buf: &'de [u8], opts: DeOptions) -> Self {
+        DeRecord {
+            buf, opts, in_row: false, nested: false, field: 0, expected: None, short: false,
+            cell: 0, current: 0, row_len: buf.len(), diagnostics: Vec::new(),
+        }
+    }
+
+    fn at_row_end(&self) -> bool {
+        self.buf.is_empty() || self.buf[0] == ROW_TERM_BYTE
+    }
+
+    /// Deserializes a whole row into a struct or tuple with `len` fields,
+    /// applying the missing and extra cell policies.
+    fn deserialize_fields<V>(&mut self, len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de> {
+        // A nested struct or tuple is read inline from the following values
+        if self.in_row {
+            return self.deserialize_seq(visitor);
+        }
+        self.in_row = true;
+        self.expected = Some(len);
+
+        let value = match visitor.visit_seq(&mut *self) {
+            Err(_) if self.short => return Err(unequal_lengths(len, self.cell)),
+            res => res?,
+        };
+
+        if self.opts.extra == ExtraCells::Reject && !self.at_row_end() {
+            let extra = self.buf.iter().filter(|&&b| b == VALUE_TERM_BYTE).count();
+            return Err(unequal_lengths(self.cell, self.cell + extra));
+        }
+
+        Ok(value)
     }
 
+    /// Consumes the next value if it is null. With [`Utf8Policy::Skip`], a
+    /// value that is not valid UTF-8 is consumed and reported as null too.
     fn next_is_null(&mut self) -> Result<bool, Error> {
-        match self.buf[0] == NULL_BYTE {
-            true => {
-                if self.buf[1] == VALUE_TERM_BYTE {
-                    self.buf = &self.buf[2..];
-                    Ok(true)
-                } else {
-                    Err(Error(ErrorKind::Deserialize(
-                        format!("Expected TERM_BYTE after NULL_BYTE, got {:?}", self.buf[1])
-                    )))
-                }
+        self.current = self.cell;
+        match self.buf {
+            [NULL_BYTE, VALUE_TERM_BYTE, ..] => {
+                self.buf = &self.buf[2..];
+                self.cell += 1;
+                return Ok(true);
+            },
+            // A stray null byte is salvaged as part of an invalid value
+            [NULL_BYTE, ..] if self.opts.utf8 == Utf8Policy::Strict => return Err(Error(ErrorKind::Deserialize(
+                format!("Expected TERM_BYTE after NULL_BYTE, got {:?}", self.buf.get(1))
+            ))),
+            _ => (),
+        }
+
+        if self.opts.utf8 != Utf8Policy::Skip {
+            return Ok(false);
+        }
+        let end = match self.buf.iter().position(|&b| b == VALUE_TERM_BYTE) {
+            Some(end) => end,
+            None => return Ok(false),
+        };
+        match from_utf8(&self.buf[..end]) {
+            Ok(_) => Ok(false),
+            Err(_) => {
+                self.salvaged(self.row_len - self.buf.len(), &self.buf[..end]);
+                self.buf = &self.buf[end + 1..];
+                self.cell += 1;
+                Ok(true)
             },
-            false => Ok(false),
         }
     }
 
-    fn next_value(&mut self) -> Result<Option<&str>, Error> {
-        // Check if value is null byte
-        if self.buf[0] == NULL_BYTE {
-            if self.buf[1] == VALUE_TERM_BYTE {
-                self.buf = &self.buf[2..];
-                return Ok(None);
-            } else {
-                return Err(Error(ErrorKind::Deserialize(
-                    format!("Expected TERM_BYTE after NULL_BYTE, got {:?}", self.buf[1])
-                )));
-            }
+    fn next_cell(&mut self) -> Result<Cell<'de>, Error> {
+        if self.next_is_null()? {
+            return Ok(Cell::Null);
         }
 
-        // Check if value is empty string
-        if self.buf[0] == VALUE_TERM_BYTE {
-            self.buf = &self.buf[1..];
-            return Ok(Some(""));
+        let end = self.buf.iter().position(|&b| b == VALUE_TERM_BYTE).ok_or(Error(ErrorKind::Deserialize(
+            "Unable to find VALUE_TERM_BYTE in record".to_owned()
+        )))?;
+        let offset = self.row_len - self.buf.len();
+        let value = &self.buf[..end];
+        self.buf = &self.buf[end + 1..];
+
+        let cell = match (from_utf8(value), self.opts.utf8) {
+            (Ok(value), _) => Ok(Cell::Str(Cow::Borrowed(value))),
+            (Err(e), Utf8Policy::Strict | Utf8Policy::Skip) => Err(e.into()),
+            (Err(_), Utf8Policy::Replace) => {
+                self.salvaged(offset, value);
+                Ok(Cell::Str(String::from_utf8_lossy(value)))
+            },
+            (Err(_), Utf8Policy::RawBytes) => {
+                self.salvaged(offset, value);
+                Ok(Cell::Raw(value))
+            },
+        };
+        self.cell += 1;
+        cell
+    }
+
+    fn next_value(&mut self) -> Result<Option<Cow<'de, str>>, Error> {
+        match self.next_cell()? {
+            Cell::Null => Ok(None),
+            Cell::Str(value) => Ok(Some(value)),
+            Cell::Raw(_) => Err(Error(ErrorKind::Deserialize(
+                "Value is not valid UTF-8 and can only be read as bytes".to_owned()
+            ))),
         }
+    }
 
-        // Parse UTF-8 String
-        for i in 1..self.buf.len() {
-            if self.buf[i] == VALUE_TERM_BYTE {
-                let value = &self.buf[0..i];
-                self.buf = &self.buf[i + 1..];
+    /// Records a diagnostic for a value with invalid UTF-8 that was salvaged.
+    /// `offset` is the position of the value within the row.
+    fn salvaged(&mut self, offset: usize, value: &[u8]) {
+        let kind = match value.contains(&NULL_BYTE) {
+            true => DiagnosticKind::StrayNullByte,
+            false => DiagnosticKind::InvalidUtf8,
+        };
+        self.diagnostics.push(Diagnostic {
+            row: 0,
+            byte_offset: offset as u64,
+            field: Some(self.cell),
+            kind,
+            raw_excerpt: value[..value.len().min(EXCERPT_LEN)].to_vec(),
+        });
+    }
 
-                return Ok(Some(from_utf8(value)?));
-            }
+    /// The index of the value that was read last, e.g. the one that failed.
+    pub(crate) fn current_field(&self) -> usize {
+        self.current
+    }
+
+    /// Diagnostics for the values salvaged so far. Their `row` is left at 0
+    /// and their `byte_offset` is relative to the row, for the reader to fix up.
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn next_parsed<T>(&mut self) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display {
+        let value = self.next_str_value()?;
+        parse(&self.opts.policy.number(&value))
+    }
+
+    fn next_float<T>(&mut self) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display {
+        let value = self.next_str_value()?;
+        parse(&self.opts.policy.float(&value))
+    }
+
+    /// Consumes the next value if it is empty and `empty_as_none` is enabled.
+    fn next_is_empty(&mut self) -> Result<bool, Error> {
+        if !self.opts.policy.empty_as_none {
+            return Ok(false);
         }
 
-        Err(Error(ErrorKind::Deserialize(
-            "Unable to find VALUE_TERM_BYTE in record".to_owned()
-        )))
+        let end = match self.buf.iter().position(|&b| b == VALUE_TERM_BYTE) {
+            Some(end) => end,
+            None => return Ok(false),
+        };
+
+        match from_utf8(&self.buf[..end]).is_ok_and(|v| self.opts.policy.trim(v).is_empty()) {
+            true => {
+                self.buf = &self.buf[end + 1..];
+                self.cell += 1;
+                Ok(true)
+            },
+            false => Ok(false),
+        }
     }
 
-    fn next_str_value(&mut self) -> Result<&str, Error> {
+    fn next_str_value(&mut self) -> Result<Cow<'de, str>, Error> {
         let value = self.next_value()?;
         let value = value.ok_or(Error(ErrorKind::Deserialize(
             "Got None but expected string".to_owned()
@@ -74,32 +230,87 @@ impl<'de> DeRecord<'de> {
     }
 }
 
-impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
+/// The row is left at 0 for the reader to fill in, see [`Error::at_row`].
+fn unequal_lengths(expected: usize, actual: usize) -> Error {
+    Error(ErrorKind::UnequalLengths { row: 0, expected, actual })
+}
+
+/// A single decoded value.
+enum Cell<'de> {
+    Null,
+    Str(Cow<'de, str>),
+    // Invalid UTF-8 kept as is by `Utf8Policy::RawBytes`
+    Raw(&'de [u8]),
+}
+
+fn visit_cow<'de, V: Visitor<'de>>(visitor: V, value: Cow<'de, str>) -> Result<V::Value, Error> {
+    match value {
+        Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+        Cow::Owned(value) => visitor.visit_string(value),
+    }
+}
+
+fn parse<T>(value: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: fmt::Display {
+    value.parse().map_err(|e| Error(ErrorKind::Deserialize(
+        format!("Failed to parse {:?} as {}: {}", value, type_name::<T>(), e)
+    )))
+}
+
+impl<'de> Deserializer<'de> for &mut DeRecord<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        todo!()
+        if !self.in_row {
+            return self.deserialize_seq(visitor);
+        }
+
+        let value = match self.next_cell()? {
+            Cell::Str(value) => value,
+            Cell::Null => return visitor.visit_none(),
+            Cell::Raw(value) => return visitor.visit_borrowed_bytes(value),
+        };
+
+        if self.opts.inference == ValueInference::Scalars {
+            if let Ok(v) = value.parse::<bool>() {
+                return visitor.visit_bool(v);
+            }
+            // Only values that are written back the same way are inferred, so
+            // `007`, `+5`, `1.0` or integers that overflow i64 stay strings
+            if let Ok(v) = value.parse::<i64>() {
+                if v.to_string() == value {
+                    return visitor.visit_i64(v);
+                }
+            }
+            if let Ok(v) = value.parse::<f64>() {
+                if v.is_finite() && value.contains('.') && v.to_string() == value {
+                    return visitor.visit_f64(v);
+                }
+            }
+        }
+
+        visit_cow(visitor, value)
     }
 
-    fn deserialize_bool<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         let value = self.next_str_value()?;
 
-        match value {
-            "true" => visitor.visit_bool(true),
-            "false" => visitor.visit_bool(false),
-            _ => Err(Error(ErrorKind::Deserialize("Failed to deserialize bool".to_owned()))),
+        match self.opts.policy.parse_bool(&value) {
+            Some(v) => visitor.visit_bool(v),
+            None => Err(Error(ErrorKind::Deserialize("Failed to deserialize bool".to_owned()))),
         }
     }
 
-    fn deserialize_i8<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_i8(value)
     }
@@ -107,8 +318,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_i16(value)
     }
@@ -116,8 +326,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_i32(value)
     }
@@ -125,8 +334,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_i64(value)
     }
@@ -134,24 +342,21 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_u8(value)}
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_u16(value)}
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_u32(value)
     }
@@ -159,8 +364,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_parsed()?;
 
         visitor.visit_u64(value)
     }
@@ -168,8 +372,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_float()?;
 
         visitor.visit_f32(value)
     }
@@ -177,8 +380,7 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        let value = value.parse().unwrap();
+        let value = self.next_float()?;
 
         visitor.visit_f64(value)
     }
@@ -199,40 +401,51 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        visitor.visit_str(value)
+        match self.next_cell()? {
+            Cell::Str(value) => visit_cow(visitor, value),
+            Cell::Raw(value) => visitor.visit_borrowed_bytes(value),
+            Cell::Null => Err(Error(ErrorKind::Deserialize(
+                "Got None but expected string".to_owned()
+            ))),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_str_value()?;
-        visitor.visit_string(value.to_owned())
+        self.deserialize_str(visitor)
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        todo!()
+        self.deserialize_byte_buf(visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        todo!()
+        match self.next_cell()? {
+            Cell::Str(value) => visitor.visit_byte_buf(self.opts.bytes.decode(&value)?),
+            // Salvaged bytes are passed on as they are
+            Cell::Raw(value) => visitor.visit_borrowed_bytes(value),
+            Cell::Null => Err(Error(ErrorKind::Deserialize(
+                "Got None but expected string".to_owned()
+            ))),
+        }
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        let value = self.next_is_null()?;
+        let value = self.next_is_null()? || self.next_is_empty()?;
         match value {
             true => visitor.visit_none(),
             false => visitor.visit_some(self)
         }
     }
 
-    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         todo!()
@@ -240,8 +453,8 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
 
     fn deserialize_unit_struct<V>(
         self,
-        name: &'static str,
-        visitor: V,
+        _name: &'static str,
+        _visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
@@ -250,8 +463,8 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
 
     fn deserialize_newtype_struct<V>(
         self,
-        name: &'static str,
-        visitor: V,
+        _name: &'static str,
+        _visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
@@ -261,61 +474,72 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(self)
+        if !self.in_row {
+            self.in_row = true;
+            return visitor.visit_seq(self);
+        }
+
+        // A field that is a sequence takes the rest of the row
+        self.nested = true;
+        let res = visitor.visit_seq(&mut *self);
+        self.nested = false;
+        res
     }
 
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        todo!()
+        self.deserialize_fields(len, visitor)
     }
 
     fn deserialize_tuple_struct<V>(
         self,
-        name: &'static str,
-        len: usize,
-        visitor: V,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         todo!()
     }
 
-    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(self)
+        Err(Error(ErrorKind::Deserialize(
+            "Rows have no field names, so maps and #[serde(flatten)] are not supported".to_owned()
+        )))
     }
 
     fn deserialize_struct<V>(
         self,
-        name: &'static str,
+        _name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
-        visitor.visit_seq(self)
+        self.deserialize_fields(fields.len(), visitor)
     }
 
     fn deserialize_enum<V>(
         self,
-        name: &'static str,
-        variants: &'static [&'static str],
-        visitor: V,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         todo!()
     }
 
-    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_identifier<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         todo!()
     }
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de> {
         todo!()
@@ -323,18 +547,18 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
 
     fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: serde::de::Visitor<'de>,
-    {
-        let _ = visitor;
-        Err(serde::de::Error::custom("i128 is not supported"))
+        V: serde::de::Visitor<'de> {
+        let value = self.next_parsed()?;
+
+        visitor.visit_i128(value)
     }
 
     fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
-        V: serde::de::Visitor<'de>,
-    {
-        let _ = visitor;
-        Err(serde::de::Error::custom("u128 is not supported"))
+        V: serde::de::Visitor<'de> {
+        let value = self.next_parsed()?;
+
+        visitor.visit_u128(value)
     }
 
     fn is_human_readable(&self) -> bool {
@@ -342,16 +566,217 @@ impl<'a, 'de> Deserializer<'de> for &'a mut DeRecord<'de> {
     }
 }
 
-impl<'a, 'de> SeqAccess<'de> for DeRecord<'de> {
+impl<'de> SeqAccess<'de> for DeRecord<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de> {
+        let expected = match (self.nested, self.expected) {
+            (false, Some(expected)) => expected,
+            _ => return match self.at_row_end() {
+                true => Ok(None),
+                false => seed.deserialize(self).map(Some),
+            },
+        };
+
+        let index = self.field;
+        self.field += 1;
+
+        if index + 1 == expected && self.opts.extra == ExtraCells::Collect {
+            return seed.deserialize(RestOfRow(self)).map(Some);
+        }
+        if !self.at_row_end() {
+            return seed.deserialize(self).map(Some);
+        }
+        if index >= expected {
+            return Ok(None);
+        }
+
+        match self.opts.missing {
+            MissingCells::Error => {
+                // Left to serde so `#[serde(default)]` fields still work
+                self.short = true;
+                Ok(None)
+            },
+            MissingCells::Null => seed.deserialize(MissingCell { default: false }).map(Some),
+            MissingCells::Default => seed.deserialize(MissingCell { default: true }).map(Some),
+        }
+    }
+}
+
+/// Deserializes a catch-all field from the remaining values of a row.
+struct RestOfRow<'a, 'de>(&'a mut DeRecord<'de>);
+
+impl<'de> Deserializer<'de> for RestOfRow<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        self.0.deserialize_seq(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Stands in for a value that is missing from the end of a row, either as
+/// null or as the default value of the requested type.
+struct MissingCell {
+    default: bool,
+}
+
+impl MissingCell {
+    fn null<T>(&self) -> Result<T, Error> {
+        Err(Error(ErrorKind::Deserialize(
+            "Got None but expected string".to_owned()
+        )))
+    }
+}
+
+macro_rules! missing_number {
+    ($($method:ident => $visit:ident($($zero:expr)?),)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de> {
+                match self.default {
+                    true => visitor.$visit($($zero)?),
+                    false => self.null(),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for MissingCell {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de> {
+        match self.default {
+            true => visitor.visit_seq(serde::de::value::SeqDeserializer::<_, Error>::new(std::iter::empty::<()>())),
+            false => self.null(),
+        }
+    }
+
+    missing_number! {
+        deserialize_bool => visit_bool(false),
+        deserialize_i8 => visit_i8(0),
+        deserialize_i16 => visit_i16(0),
+        deserialize_i32 => visit_i32(0),
+        deserialize_i64 => visit_i64(0),
+        deserialize_i128 => visit_i128(0),
+        deserialize_u8 => visit_u8(0),
+        deserialize_u16 => visit_u16(0),
+        deserialize_u32 => visit_u32(0),
+        deserialize_u64 => visit_u64(0),
+        deserialize_u128 => visit_u128(0),
+        deserialize_f32 => visit_f32(0.0),
+        deserialize_f64 => visit_f64(0.0),
+        deserialize_char => visit_char('\0'),
+        deserialize_str => visit_str(""),
+        deserialize_string => visit_str(""),
+        deserialize_bytes => visit_bytes(b""),
+        deserialize_byte_buf => visit_bytes(b""),
+        deserialize_unit => visit_unit(),
+    }
+
+    serde::forward_to_deserialize_any! {
+        unit_struct newtype_struct tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes a whole document as a sequence of records.
+struct DeDocument<'de> {
+    buf: &'de [u8],
+    opts: DeOptions,
+    rows: u64,
+}
+
+impl<'de> Deserializer<'de> for &mut DeDocument<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de> {
+        visitor.visit_seq(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> SeqAccess<'de> for DeDocument<'de> {
     type Error = Error;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
     where
         T: serde::de::DeserializeSeed<'de> {
-        if self.buf.len() == 0 || self.buf[0] == ROW_TERM_BYTE {
+        if self.buf.is_empty() {
             return Ok(None);
         }
 
-        seed.deserialize(self).map(Some)
+        self.rows += 1;
+
+        // Like `FinalRowPolicy::Error`, a final row without its terminator is
+        // an error rather than being read as if it was complete
+        let end = match self.buf.iter().position(|&b| b == ROW_TERM_BYTE) {
+            Some(i) => i + 1,
+            None => {
+                let truncation = match self.buf.last() {
+                    Some(&VALUE_TERM_BYTE) => Truncation::MidRow,
+                    _ => Truncation::MidValue,
+                };
+                return Err(Error(ErrorKind::Truncated { row: self.rows, truncation }));
+            },
+        };
+        let (row, rest) = self.buf.split_at(end);
+        self.buf = rest;
+
+        seed.deserialize(&mut DeRecord::from_ref(row, self.opts.clone()))
+            .map(Some)
+            .map_err(|e| e.at_row(self.rows))
     }
 }
+
+/// Deserializes a whole RSV document, e.g. into a `Vec<T>` with one item per row.
+///
+/// A final row that is missing its terminator fails with
+/// [`ErrorKind::Truncated`], as with the default [`crate::FinalRowPolicy`].
+pub fn from_slice<'de, T: Deserialize<'de>>(buf: &'de [u8]) -> Result<T, Error> {
+    T::deserialize(&mut DeDocument { buf, opts: DeOptions::default(), rows: 0 })
+}
+
+/// Reads `rdr` to the end and deserializes it as a whole RSV document.
+pub fn from_reader<R: Read, T: DeserializeOwned>(mut rdr: R) -> Result<T, Error> {
+    let mut buf = Vec::new();
+    rdr.read_to_end(&mut buf)?;
+
+    from_slice(&buf)
+}
+
+/// Deserializes a single row. The trailing `ROW_TERM_BYTE` is optional.
+pub fn from_record_bytes<'de, T: Deserialize<'de>>(buf: &'de [u8]) -> Result<T, Error> {
+    T::deserialize(&mut DeRecord::from_ref(buf, DeOptions::default())).map_err(|e| e.at_row(1))
+}
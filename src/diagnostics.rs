@@ -0,0 +1,52 @@
+//! Reports about problems that were recovered from while reading.
+
+use crate::policy::{Limit, Truncation};
+
+/// How many bytes of the offending value or row are kept in [`Diagnostic::raw_excerpt`].
+pub(crate) const EXCERPT_LEN: usize = 32;
+
+/// A problem in the input that the reader recovered from instead of failing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based row number.
+    pub row: u64,
+    /// Offset from the start of the input to the offending value, or to the
+    /// row if the problem is not tied to a single value.
+    pub byte_offset: u64,
+    /// 0-based index of the offending value within the row, if known.
+    pub field: Option<usize>,
+    pub kind: DiagnosticKind,
+    /// The start of the offending value or row.
+    pub raw_excerpt: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A value was not valid UTF-8 and was salvaged.
+    InvalidUtf8,
+    /// A value contained a `NULL_BYTE` that did not form a null value, and
+    /// was salvaged.
+    StrayNullByte,
+    /// The row was skipped because it could not be deserialized.
+    Malformed(String),
+    /// The row was skipped because it exceeded one of the reader's limits.
+    LimitExceeded(Limit),
+    /// The row was skipped because it had the wrong number of fields.
+    UnequalLengths { expected: usize, actual: usize },
+    /// The final row was skipped because it was missing its terminator.
+    Truncated(Truncation),
+}
+
+/// Counts of what happened while reading with error recovery.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Rows read, including skipped rows.
+    pub rows_read: u64,
+    /// Rows skipped because of an error.
+    pub rows_skipped: u64,
+    /// Problems found, including those not kept because the list was full.
+    pub diagnostics: u64,
+    /// Problems that were counted but not kept.
+    pub diagnostics_dropped: u64,
+}
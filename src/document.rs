@@ -0,0 +1,151 @@
+//! Whole-document API matching the RSV reference implementations
+//! (`encodeRsv`, `decodeRsv`, `isValidRsv`, `loadRsv`, `saveRsv` and `appendRsv`).
+//!
+//! Rows are plain `Vec<Vec<Option<String>>>` where `None` is the RSV null value.
+//! For streaming or serde support use [`crate::reader::Reader`] and
+//! [`crate::writer::Writer`] instead.
+
+use std::{fs::{self, OpenOptions}, io::{Read, Seek, SeekFrom, Write}, mem, path::Path, str::from_utf8};
+
+use crate::error::{Error, ErrorKind};
+use crate::utils::{NULL_BYTE, ROW_TERM_BYTE, VALUE_TERM_BYTE};
+
+pub type Rows = Vec<Vec<Option<String>>>;
+
+/// An in-memory RSV document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RsvDocument {
+    pub rows: Rows,
+}
+
+impl RsvDocument {
+    pub fn new(rows: Rows) -> Self {
+        RsvDocument { rows }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        encode_rsv(&self.rows)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(RsvDocument::new(decode_rsv(bytes)?))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(RsvDocument::new(load_rsv(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        save_rsv(&self.rows, path)
+    }
+
+    pub fn append_to<P: AsRef<Path>>(&self, path: P, continue_last_row: bool) -> Result<(), Error> {
+        append_rsv(&self.rows, path, continue_last_row)
+    }
+}
+
+impl From<Rows> for RsvDocument {
+    fn from(rows: Rows) -> Self {
+        RsvDocument::new(rows)
+    }
+}
+
+fn incomplete(what: &str) -> Error {
+    Error(ErrorKind::Deserialize(format!("Incomplete RSV {}", what)))
+}
+
+pub fn encode_rsv(rows: &[Vec<Option<String>>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for row in rows {
+        for value in row {
+            match value {
+                Some(v) => out.extend_from_slice(v.as_bytes()),
+                None => out.push(NULL_BYTE),
+            }
+            out.push(VALUE_TERM_BYTE);
+        }
+        out.push(ROW_TERM_BYTE);
+    }
+    out
+}
+
+pub fn decode_rsv(bytes: &[u8]) -> Result<Rows, Error> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut start = 0;
+
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            VALUE_TERM_BYTE => {
+                row.push(match &bytes[start..i] {
+                    [NULL_BYTE] => None,
+                    value => Some(from_utf8(value)?.to_owned()),
+                });
+                start = i + 1;
+            },
+            ROW_TERM_BYTE => {
+                if start != i {
+                    return Err(incomplete("row"));
+                }
+                rows.push(mem::take(&mut row));
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+
+    if start != bytes.len() || !row.is_empty() {
+        return Err(incomplete("document"));
+    }
+
+    Ok(rows)
+}
+
+/// Returns `true` if `bytes` is a complete RSV document in which every value
+/// is either null or valid UTF-8.
+pub fn is_valid_rsv(bytes: &[u8]) -> bool {
+    decode_rsv(bytes).is_ok()
+}
+
+pub fn load_rsv<P: AsRef<Path>>(path: P) -> Result<Rows, Error> {
+    decode_rsv(&fs::read(path)?)
+}
+
+pub fn save_rsv<P: AsRef<Path>>(rows: &[Vec<Option<String>>], path: P) -> Result<(), Error> {
+    Ok(fs::write(path, encode_rsv(rows))?)
+}
+
+/// Appends `rows` to the file at `path`, creating it if needed.
+///
+/// Fails without writing anything if the existing file does not end with a
+/// complete row. With `continue_last_row` the first appended row is merged
+/// into the last row of the file.
+pub fn append_rsv<P: AsRef<Path>>(rows: &[Vec<Option<String>>], path: P, continue_last_row: bool) -> Result<(), Error> {
+    let mut f = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+    let len = f.seek(SeekFrom::End(0))?;
+
+    if len > 0 {
+        let mut tail = vec![0; len.min(2) as usize];
+        f.seek(SeekFrom::End(-(tail.len() as i64)))?;
+        f.read_exact(&mut tail)?;
+
+        match tail[..] {
+            [.., last] if last != ROW_TERM_BYTE => return Err(incomplete("document")),
+            [prev, _] if prev != VALUE_TERM_BYTE && prev != ROW_TERM_BYTE => return Err(incomplete("row")),
+            _ => (),
+        }
+
+        if continue_last_row {
+            f.set_len(len - 1)?;
+        }
+    }
+
+    let mut encoded = encode_rsv(rows);
+    if continue_last_row && rows.is_empty() && len > 0 {
+        encoded.push(ROW_TERM_BYTE);
+    }
+
+    f.seek(SeekFrom::End(0))?;
+    f.write_all(&encoded)?;
+    Ok(f.flush()?)
+}
@@ -0,0 +1,131 @@
+use crate::error::{Error, ErrorKind};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const HEX_ALPHABET: &[u8; 16] = b"0123456789abcdef";
+
+/// Controls how byte fields are stored inside an RSV value.
+///
+/// Raw bytes may contain the reserved RSV bytes, so byte fields are always
+/// written as text. Reader and writer must use the same encoding.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteEncoding {
+    /// Lowercase hexadecimal, two characters per byte.
+    #[default]
+    Hex,
+    /// Standard base64 alphabet with padding.
+    Base64,
+    /// Refuse to read or write byte fields.
+    Reject,
+}
+
+impl ByteEncoding {
+    pub(crate) fn encode(&self, v: &[u8]) -> Result<String, Error> {
+        match self {
+            ByteEncoding::Hex => Ok(encode_hex(v)),
+            ByteEncoding::Base64 => Ok(encode_base64(v)),
+            ByteEncoding::Reject => Err(Error(ErrorKind::Serialize(
+                "Byte fields are rejected by the writer".to_owned()
+            ))),
+        }
+    }
+
+    pub(crate) fn decode(&self, v: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            ByteEncoding::Hex => decode_hex(v),
+            ByteEncoding::Base64 => decode_base64(v),
+            ByteEncoding::Reject => Err(Error(ErrorKind::Deserialize(
+                "Byte fields are rejected by the reader".to_owned()
+            ))),
+        }
+    }
+}
+
+fn encode_hex(v: &[u8]) -> String {
+    let mut out = String::with_capacity(v.len() * 2);
+    for b in v {
+        out.push(HEX_ALPHABET[(b >> 4) as usize] as char);
+        out.push(HEX_ALPHABET[(b & 0x0F) as usize] as char);
+    }
+    out
+}
+
+fn decode_hex(v: &str) -> Result<Vec<u8>, Error> {
+    fn nibble(c: u8) -> Result<u8, Error> {
+        match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            b'a'..=b'f' => Ok(c - b'a' + 10),
+            b'A'..=b'F' => Ok(c - b'A' + 10),
+            _ => Err(Error(ErrorKind::Deserialize(
+                format!("Invalid hex character {:?}", c as char)
+            ))),
+        }
+    }
+
+    let v = v.as_bytes();
+    if !v.len().is_multiple_of(2) {
+        return Err(Error(ErrorKind::Deserialize(
+            "Hex value has an odd number of characters".to_owned()
+        )));
+    }
+
+    v.chunks(2)
+        .map(|c| Ok(nibble(c[0])? << 4 | nibble(c[1])?))
+        .collect()
+}
+
+fn encode_base64(v: &[u8]) -> String {
+    let mut out = String::with_capacity(v.len().div_ceil(3) * 4);
+    for chunk in v.chunks(3) {
+        let n = match chunk.len() {
+            1 => (chunk[0] as u32) << 16,
+            2 => (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8,
+            _ => (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32,
+        };
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - i * 6) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(v: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error(ErrorKind::Deserialize(
+        "Invalid base64 value".to_owned()
+    ));
+
+    let v = v.as_bytes();
+    if !v.len().is_multiple_of(4) {
+        return Err(invalid());
+    }
+
+    let mut out = Vec::with_capacity(v.len() / 4 * 3);
+    for (i, chunk) in v.chunks(4).enumerate() {
+        let last = i == v.len() / 4 - 1;
+        let mut n = 0u32;
+        let mut len = 0;
+
+        for (j, &c) in chunk.iter().enumerate() {
+            let sextet = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                // Padding is only allowed at the end of the final chunk
+                b'=' if last && j >= 2 && chunk[j..].iter().all(|&c| c == b'=') => break,
+                _ => return Err(invalid()),
+            };
+            n |= (sextet as u32) << (18 - j * 6);
+            len += 1;
+        }
+
+        out.extend_from_slice(&n.to_be_bytes()[1..len]);
+    }
+
+    Ok(out)
+}
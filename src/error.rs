@@ -1,4 +1,5 @@
-use std::fmt;
+use std::{fmt, path::PathBuf};
+use crate::policy::{Limit, Truncation};
 use std::error::Error as StdError;
 use std::str::Utf8Error;
 // #[cfg(features = "serde")]
@@ -14,13 +15,106 @@ pub enum ErrorKind {
     Serialize(String),
     Deserialize(String),
     IO(String),
+    /// A row had a different number of fields than the first row while
+    /// flexible rows were disabled. `row` is 1-based.
+    UnequalLengths { row: u64, expected: usize, actual: usize },
+    /// An error while reading one of several files, see [`crate::multi::MultiReader`].
+    /// `row` is 1-based, or 0 if the file could not be opened.
+    InFile { path: PathBuf, row: u64, err: Box<Error> },
+    /// A row exceeded one of the reader's [`crate::policy::Limits`]. `row` is 1-based.
+    LimitExceeded { row: u64, limit: Limit, max: usize },
+    /// The input ended in the middle of a row. `row` is 1-based.
+    Truncated { row: u64, truncation: Truncation },
+    /// A trailer could not be verified because rows before it were skipped
+    /// for exceeding a limit, see [`crate::trailer`].
+    UnverifiedTrailer { skipped: u64 },
+}
+
+impl Error {
+    /// Fills in the row of an error raised while deserializing a single row,
+    /// where the position of the row in the input isn't known.
+    pub(crate) fn at_row(mut self, n: u64) -> Error {
+        if let ErrorKind::UnequalLengths { row: row @ 0, .. } = &mut self.0 {
+            *row = n;
+        }
+        self
+    }
 }
 
 impl StdError for Error {}
 
 impl fmt::Display for Error {
-    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
-        todo!()
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            ErrorKind::Serialize(msg) => write!(f, "serialize error: {}", msg),
+            ErrorKind::Deserialize(msg) => write!(f, "deserialize error: {}", msg),
+            ErrorKind::IO(msg) => write!(f, "io error: {}", msg),
+            ErrorKind::UnequalLengths { row, expected, actual } => write!(
+                f, "row {} has {} fields, expected {}", row, actual, expected
+            ),
+            ErrorKind::InFile { path, row, err } => write!(f, "{}, row {}: {}", path.display(), row, err),
+            ErrorKind::LimitExceeded { row, limit, max } => {
+                let what = match limit {
+                    Limit::RowBytes => "bytes",
+                    Limit::Fields => "fields",
+                    Limit::ValueBytes => "bytes in a value",
+                };
+                write!(f, "row {} has more than {} {}", row, max, what)
+            },
+            ErrorKind::Truncated { row, truncation } => match truncation {
+                Truncation::MidValue => write!(f, "input ends in the middle of a value in row {}", row),
+                Truncation::MidRow => write!(f, "input ends before the terminator of row {}", row),
+            },
+            ErrorKind::UnverifiedTrailer { skipped } => write!(
+                f, "trailer can't be verified, {} rows before it exceeded a limit and were skipped", skipped
+            ),
+        }
+    }
+}
+
+/// Returned when a writer could not be flushed while taking it apart. The
+/// writer is kept so the caller can retry or recover the unwritten data.
+pub struct IntoInnerError<W> {
+    // Boxed to keep `Result<_, IntoInnerError<_>>` small
+    wtr: Box<W>,
+    err: Error,
+}
+
+impl<W> IntoInnerError<W> {
+    pub(crate) fn new(wtr: W, err: Error) -> IntoInnerError<W> {
+        IntoInnerError { wtr: Box::new(wtr), err }
+    }
+
+    pub fn error(&self) -> &Error {
+        &self.err
+    }
+
+    pub fn into_error(self) -> Error {
+        self.err
+    }
+
+    pub fn into_inner(self) -> W {
+        *self.wtr
+    }
+}
+
+impl<W> fmt::Debug for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.err.fmt(f)
+    }
+}
+
+impl<W> fmt::Display for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.err.fmt(f)
+    }
+}
+
+impl<W> StdError for IntoInnerError<W> {}
+
+impl<W> From<IntoInnerError<W>> for Error {
+    fn from(e: IntoInnerError<W>) -> Error {
+        e.err
     }
 }
 
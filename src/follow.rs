@@ -0,0 +1,231 @@
+use std::{fs::{self, File}, io::{self, Read, Seek, SeekFrom}, path::{Path, PathBuf}, thread, time::Duration};
+
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, ErrorKind};
+use crate::reader::{DesRecordIter, Reader};
+use crate::sink::last_row_end;
+use crate::utils::ROW_TERM_BYTE;
+
+/// Reads rows from a file that another process is appending to, like `tail -f`.
+///
+/// Only complete rows are returned. When the end of the file is reached in the
+/// middle of a row, the reader waits for the rest of it. If the file shrinks
+/// below what was already read it is read again from the start, and if `path`
+/// is replaced by a new file (compared by device and inode), the old file is
+/// read to its end before switching to the new one. A partial row left at the
+/// end of a truncated or rotated file is discarded.
+///
+/// Rows are read by a [`Reader`], configured with [`FollowReader::with_reader`].
+/// New data is detected by polling, every 250ms by default.
+pub struct FollowReader {
+    rdr: Reader<FollowSource>,
+}
+
+impl FollowReader {
+    /// Opens `path` and follows it from the start.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<FollowReader, Error> {
+        let path = path.as_ref().to_owned();
+        let file = File::open(&path)?;
+
+        let source = FollowSource {
+            id: file_id(&file.metadata()?),
+            file,
+            path,
+            pos: 0,
+            ready: Vec::new(),
+            consumed: 0,
+            partial: Vec::new(),
+            max_partial: None,
+            poll: Duration::from_millis(250),
+        };
+        Ok(FollowReader { rdr: Reader::from_reader(source) })
+    }
+
+    /// Skips the rows already in the file, so only rows appended from now on
+    /// are returned. A partial row at the end is completed and returned.
+    pub fn from_end(mut self) -> Result<FollowReader, Error> {
+        let source = self.rdr.get_mut();
+        let len = source.file.seek(SeekFrom::End(0))?;
+        // Back up to the start of an unfinished row so it's returned in full
+        let start = last_row_end(&mut source.file, len)?;
+        source.file.seek(SeekFrom::Start(start))?;
+        source.pos = start;
+        Ok(self)
+    }
+
+    /// Sets how long to wait before checking the file for new data again.
+    pub fn with_poll_interval(mut self, poll: Duration) -> Self {
+        self.rdr.get_mut().poll = poll;
+        self
+    }
+
+    /// Applies `configure` to the [`Reader`] the rows are read with, e.g. to
+    /// set [`Reader::with_limits`] or [`Reader::with_error_recovery`]. The
+    /// input never ends, so the final row policy and trailer verification
+    /// have no effect.
+    pub fn with_reader<F>(mut self, configure: F) -> Self
+    where
+        F: FnOnce(Reader<FollowSource>) -> Reader<FollowSource> {
+        self.rdr = configure(self.rdr);
+        // Hand over a row that is too long instead of buffering it, so the
+        // reader rejects it
+        let max_partial = self.rdr.limits().max_row_bytes;
+        self.rdr.get_mut().max_partial = max_partial;
+        self
+    }
+
+    /// Returns a never ending iterator over the rows of the file, waiting for
+    /// new rows as needed.
+    pub fn deserialize<D: DeserializeOwned>(&mut self) -> DesRecordIter<'_, D, FollowSource> {
+        self.rdr.deserialize()
+    }
+
+    /// Waits for the next complete row and appends it to `buf`, including its
+    /// `ROW_TERM_BYTE`.
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> Result<(), Error> {
+        self.rdr.read_record(buf).unwrap_or_else(|| Err(Error(ErrorKind::IO(
+            "Followed file ended".to_owned()
+        ))))
+    }
+
+    /// Appends the next row to `buf` if it is complete, otherwise returns
+    /// `false` without waiting.
+    pub fn try_read_record(&mut self, buf: &mut Vec<u8>) -> Result<bool, Error> {
+        // The source only hands out complete rows, so anything buffered by
+        // the reader is the start of one
+        if !self.rdr.has_buffered() && !self.rdr.get_mut().poll_ready()? {
+            return Ok(false);
+        }
+
+        self.read_record(buf)?;
+        Ok(true)
+    }
+
+    /// The number of rows read so far, across truncations and rotations.
+    pub fn position(&self) -> u64 {
+        self.rdr.position()
+    }
+
+    /// The [`Reader`] the rows are read with, e.g. for its diagnostics.
+    pub fn reader(&self) -> &Reader<FollowSource> {
+        &self.rdr
+    }
+}
+
+/// The input of a [`FollowReader`]: the followed file as a stream that never
+/// ends and only contains complete rows.
+pub struct FollowSource {
+    path: PathBuf,
+    file: File,
+    id: Option<(u64, u64)>,
+    // Bytes read from the current file
+    pos: u64,
+    // Complete rows not handed out yet, starting at `consumed`
+    ready: Vec<u8>,
+    consumed: usize,
+    // Bytes of a row that is not complete yet
+    partial: Vec<u8>,
+    max_partial: Option<usize>,
+    poll: Duration,
+}
+
+impl FollowSource {
+    /// Reads what was appended to the file, and returns whether there are
+    /// complete rows to hand out. Doesn't wait.
+    fn poll_ready(&mut self) -> io::Result<bool> {
+        loop {
+            if self.consumed < self.ready.len() {
+                return Ok(true);
+            }
+            if !self.fill()? && !self.reopen()? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Reads the next chunk of the current file, returning `false` at its end.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0; 8192];
+        let len = self.file.read(&mut chunk)?;
+        if len == 0 {
+            return Ok(false);
+        }
+        self.pos += len as u64;
+        self.partial.extend_from_slice(&chunk[..len]);
+
+        if self.consumed == self.ready.len() {
+            self.ready.clear();
+            self.consumed = 0;
+        }
+        let end = match self.partial.iter().rposition(|&b| b == ROW_TERM_BYTE) {
+            Some(i) => i + 1,
+            None if self.max_partial.is_some_and(|max| self.partial.len() > max) => self.partial.len(),
+            None => 0,
+        };
+        self.ready.extend(self.partial.drain(..end));
+        Ok(true)
+    }
+
+    /// Starts over if the file was truncated or `path` now points to another
+    /// file. Returns whether there is anything new to read.
+    fn reopen(&mut self) -> io::Result<bool> {
+        let id = match fs::metadata(&self.path) {
+            Ok(meta) => file_id(&meta),
+            // Rotated away and not recreated yet
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        if id != self.id {
+            let f = match File::open(&self.path) {
+                Ok(f) => f,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+                Err(e) => return Err(e),
+            };
+            // The writer may have appended more rows after the old file was
+            // last read and before it was renamed
+            if self.fill()? {
+                return Ok(true);
+            }
+            self.id = file_id(&f.metadata()?);
+            self.file = f;
+        } else if self.file.metadata()?.len() < self.pos {
+            self.file.seek(SeekFrom::Start(0))?;
+        } else {
+            return Ok(false);
+        }
+
+        self.pos = 0;
+        self.partial.clear();
+        Ok(true)
+    }
+}
+
+impl Read for FollowSource {
+    /// Waits until there is a complete row, so it never returns 0 for a
+    /// non-empty `buf`.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while !self.poll_ready()? {
+            thread::sleep(self.poll);
+        }
+
+        let ready = &self.ready[self.consumed..];
+        let len = ready.len().min(buf.len());
+        buf[..len].copy_from_slice(&ready[..len]);
+        self.consumed += len;
+        Ok(len)
+    }
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+// Without inodes only truncation can be detected
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
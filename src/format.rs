@@ -0,0 +1,80 @@
+use std::fmt::{Display, LowerExp};
+
+use crate::error::{Error, ErrorKind};
+
+/// Controls how the writer formats `f32` and `f64` values.
+///
+/// With the default [`FloatNotation::Shortest`] and [`FloatNotation::Scientific`]
+/// notations every value, including `-0.0`, NaN and the infinities, is parsed
+/// back to exactly the same value by [`crate::reader::Reader`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FloatFormat {
+    pub notation: FloatNotation,
+    pub non_finite: NonFiniteSpelling,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatNotation {
+    /// The shortest decimal that round-trips, e.g. `0.1` or `1000000`.
+    #[default]
+    Shortest,
+    /// The shortest mantissa that round-trips, in scientific notation, e.g. `1e6`.
+    Scientific,
+    /// A fixed number of digits after the decimal point. Lossy.
+    Fixed(usize),
+    /// A fixed number of significant digits, switching to scientific notation
+    /// for very large or small exponents like C's `%g`. Lossy.
+    Significant(usize),
+}
+
+/// How NaN and the infinities are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFiniteSpelling {
+    /// `NaN`, `inf` and `-inf`.
+    #[default]
+    Short,
+    /// `NaN`, `Infinity` and `-Infinity`.
+    Long,
+    /// Refuse to write non-finite values.
+    Reject,
+}
+
+impl FloatFormat {
+    pub(crate) fn format<F>(&self, v: F) -> Result<String, Error>
+    where
+        F: Display + LowerExp + Into<f64> + Copy {
+        let wide: f64 = v.into();
+
+        if !wide.is_finite() {
+            let spelling = match (self.non_finite, wide.is_nan(), wide > 0.0) {
+                (NonFiniteSpelling::Reject, _, _) => return Err(Error(ErrorKind::Serialize(
+                    format!("Refusing to write non-finite float {}", wide)
+                ))),
+                (_, true, _) => "NaN",
+                (NonFiniteSpelling::Short, _, true) => "inf",
+                (NonFiniteSpelling::Short, _, false) => "-inf",
+                (NonFiniteSpelling::Long, _, true) => "Infinity",
+                (NonFiniteSpelling::Long, _, false) => "-Infinity",
+            };
+            return Ok(spelling.to_owned());
+        }
+
+        Ok(match self.notation {
+            FloatNotation::Shortest => v.to_string(),
+            FloatNotation::Scientific => format!("{:e}", v),
+            FloatNotation::Fixed(decimals) => format!("{:.*}", decimals, wide),
+            FloatNotation::Significant(digits) => {
+                let digits = digits.max(1);
+                let sci = format!("{:.*e}", digits - 1, wide);
+                // The exponent is taken after rounding, so 9.99 to 2 digits is 1.0e1
+                let exp: i64 = sci[sci.find('e').unwrap() + 1..].parse().unwrap();
+
+                if exp < -4 || exp >= digits as i64 {
+                    sci
+                } else {
+                    format!("{:.*}", (digits as i64 - 1 - exp) as usize, wide)
+                }
+            },
+        })
+    }
+}
@@ -1,7 +1,37 @@
+pub mod compression;
+pub mod diagnostics;
+pub mod document;
+pub mod encoding;
 pub mod error;
+pub mod follow;
+pub mod format;
 pub mod writer;
+pub mod multi;
+pub mod partitioned;
+pub mod policy;
 pub mod reader;
+pub mod rotating;
+pub mod serde_helpers;
+pub mod sink;
+pub mod source;
+pub mod trailer;
 pub mod utils;
+pub mod value;
 
 mod deserializer;
 mod serializer;
+
+pub use deserializer::{from_reader, from_record_bytes, from_slice};
+pub use document::{append_rsv, decode_rsv, encode_rsv, is_valid_rsv, load_rsv, save_rsv, RsvDocument};
+pub use encoding::ByteEncoding;
+pub use format::{FloatFormat, FloatNotation, NonFiniteSpelling};
+pub use policy::{Durability, ExtraCells, FinalRowPolicy, Limit, Limits, MissingCells, ParsePolicy, TornRowPolicy, Truncation, Utf8Policy};
+pub use serializer::{to_vec, to_writer};
+pub use value::{Value, ValueInference};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+    #[cfg(feature = "time")]
+    pub use time;
+}
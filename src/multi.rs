@@ -0,0 +1,227 @@
+use std::{collections::VecDeque, fs, io, marker::PhantomData, path::{Component, Path, PathBuf}, sync::Arc};
+
+use serde::de::DeserializeOwned;
+
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, ErrorKind};
+use crate::reader::Reader;
+use crate::source::FileSource;
+
+type Configure = Box<dyn Fn(Reader<FileSource>) -> Reader<FileSource> + Send>;
+
+/// Where a record came from. `row` is 1-based and counts header rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    pub path: Arc<Path>,
+    pub row: u64,
+}
+
+/// Reads several files one after the other as a single stream of records.
+///
+/// Every record is returned with its [`Source`], and every error is wrapped in
+/// [`ErrorKind::InFile`]. With [`MultiReader::with_header`], the first row of
+/// each file is a header: it is returned by [`MultiReader::header`] instead of
+/// as a record, and the headers of later files must match the first one.
+///
+/// Each file is read by its own [`Reader`], configured with
+/// [`MultiReader::with_reader`].
+pub struct MultiReader {
+    paths: VecDeque<PathBuf>,
+    current: Option<(Arc<Path>, Reader<FileSource>)>,
+    has_header: bool,
+    header: Option<Vec<u8>>,
+    configure: Option<Configure>,
+    diagnostics: Vec<(Arc<Path>, Diagnostic)>,
+}
+
+impl MultiReader {
+    /// Reads `paths` in the given order.
+    pub fn from_paths<I, P>(paths: I) -> MultiReader
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path> {
+        MultiReader {
+            paths: paths.into_iter().map(|p| p.as_ref().to_owned()).collect(),
+            current: None,
+            has_header: false,
+            header: None,
+            configure: None,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Reads every file matching `pattern` in lexicographic order.
+    ///
+    /// Each path component may contain `*`, matching any run of characters,
+    /// and `?`, matching a single character. Neither matches a leading `.`.
+    /// Fails if no file matches.
+    pub fn from_glob(pattern: &str) -> Result<MultiReader, Error> {
+        let paths = glob(pattern)?;
+        if paths.is_empty() {
+            return Err(Error(ErrorKind::IO(format!("No files match {}", pattern))));
+        }
+        Ok(MultiReader::from_paths(paths))
+    }
+
+    /// Treats the first row of every file as a header. Defaults to `false`.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.has_header = header;
+        self
+    }
+
+    /// Applies `configure` to the [`Reader`] of every file, e.g. to set
+    /// [`Reader::with_limits`] or [`Reader::with_error_recovery`]. Settings
+    /// that are checked across rows, like [`Reader::with_flexible`], are
+    /// checked separately for every file.
+    pub fn with_reader<F>(mut self, configure: F) -> Self
+    where
+        F: Fn(Reader<FileSource>) -> Reader<FileSource> + Send + 'static {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Problems recovered from in the files read to the end so far, with the
+    /// file they were found in. See [`Reader::diagnostics`].
+    pub fn diagnostics(&self) -> &[(Arc<Path>, Diagnostic)] {
+        &self.diagnostics
+    }
+
+    /// The header row, including its `ROW_TERM_BYTE`, once the first file was opened.
+    pub fn header(&self) -> Option<&[u8]> {
+        self.header.as_deref()
+    }
+
+    /// Returns an iterator that decodes records from all files.
+    pub fn deserialize<D: DeserializeOwned>(&mut self) -> MultiRecordIter<'_, D> {
+        MultiRecordIter {
+            _priv: PhantomData,
+            rdr: self,
+        }
+    }
+
+    /// Reads the raw bytes of the next row, including its `ROW_TERM_BYTE`, into `buf`.
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> Option<Result<Source, Error>> {
+        self.next_from_file(|rdr| rdr.read_record(buf)).map(|res| res.map(|(source, ())| source))
+    }
+
+    /// Calls `read` on the reader of the current file until it returns a
+    /// value, moving on to the next file whenever one is exhausted.
+    fn next_from_file<T, F>(&mut self, mut read: F) -> Option<Result<(Source, T), Error>>
+    where
+        F: FnMut(&mut Reader<FileSource>) -> Option<Result<T, Error>> {
+        loop {
+            if self.current.is_none() {
+                let path = self.paths.pop_front()?;
+                if let Err(e) = self.open(&path) {
+                    return Some(Err(e));
+                }
+            }
+
+            let (path, rdr) = self.current.as_mut().unwrap();
+            match read(rdr) {
+                Some(res) => {
+                    let source = Source { path: path.clone(), row: rdr.position() };
+                    return Some(res
+                        .map(|v| (source, v))
+                        .map_err(|e| in_file(path, rdr.position(), e)));
+                },
+                None => {
+                    let (path, mut rdr) = self.current.take().unwrap();
+                    self.diagnostics.extend(rdr.take_diagnostics().into_iter().map(|d| (path.clone(), d)));
+                },
+            }
+        }
+    }
+
+    fn open(&mut self, path: &Path) -> Result<(), Error> {
+        let mut rdr = Reader::from_path(path).map_err(|e| in_file(path, 0, e))?;
+        if let Some(configure) = &self.configure {
+            rdr = configure(rdr);
+        }
+
+        if self.has_header {
+            let mut header = Vec::new();
+            if let Some(res) = rdr.read_record(&mut header) {
+                res.map_err(|e| in_file(path, 1, e))?;
+                match &self.header {
+                    None => self.header = Some(header),
+                    Some(first) if *first != header => return Err(in_file(path, 1, Error(ErrorKind::Deserialize(
+                        "Header does not match the header of the first file".to_owned()
+                    )))),
+                    Some(_) => (),
+                }
+            }
+        }
+
+        self.current = Some((Arc::from(path), rdr));
+        Ok(())
+    }
+}
+
+fn in_file(path: &Path, row: u64, err: Error) -> Error {
+    Error(ErrorKind::InFile { path: path.to_owned(), row, err: Box::new(err) })
+}
+
+pub struct MultiRecordIter<'a, D: DeserializeOwned> {
+    rdr: &'a mut MultiReader,
+    _priv: PhantomData<D>
+}
+
+impl<D: DeserializeOwned> Iterator for MultiRecordIter<'_, D> {
+    type Item = Result<(Source, D), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rdr.next_from_file(|rdr| rdr.deserialize().next())
+    }
+}
+
+/// Expands `pattern` into the sorted list of existing paths it matches.
+fn glob(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![PathBuf::new()];
+
+    for component in Path::new(pattern).components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            other => {
+                paths.iter_mut().for_each(|p| p.push(other));
+                continue;
+            },
+        };
+        if !part.contains(['*', '?']) {
+            paths.iter_mut().for_each(|p| p.push(&*part));
+            continue;
+        }
+
+        let mut matched = Vec::new();
+        for dir in &paths {
+            let entries = match fs::read_dir(if dir.as_os_str().is_empty() { Path::new(".") } else { dir }) {
+                Ok(entries) => entries,
+                Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::NotADirectory) => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let name = entry?.file_name();
+                let name = name.to_string_lossy();
+                if !name.starts_with('.') && wildcard_match(part.as_bytes(), name.as_bytes()) {
+                    matched.push(dir.join(&*name));
+                }
+            }
+        }
+        paths = matched;
+    }
+
+    paths.retain(|p| p.is_file());
+    paths.sort();
+    Ok(paths)
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => wildcard_match(&pattern[1..], name)
+            || (!name.is_empty() && wildcard_match(pattern, &name[1..])),
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
@@ -0,0 +1,262 @@
+use std::{borrow::Cow, collections::{HashMap, HashSet}, fmt::{Display, Write as _}, fs, hash::Hash, io, path::{Path, PathBuf}};
+
+use serde::Serialize;
+
+use crate::encoding::ByteEncoding;
+use crate::error::{Error, ErrorKind};
+use crate::format::FloatFormat;
+use crate::policy::TornRowPolicy;
+use crate::sink::FileSink;
+use crate::utils::{NULL_BYTE, VALUE_TERM_BYTE};
+use crate::writer::Writer;
+
+type KeyFn<K> = Box<dyn Fn(&[Option<&str>]) -> K + Send>;
+
+struct Partition {
+    wtr: Writer<FileSink>,
+    last_used: u64,
+}
+
+/// Routes rows into one file per partition key, laid out as
+/// `dir/<name>=<key>/part-0.rsv`.
+///
+/// The key is computed from the encoded values of each row, either by a
+/// function or by looking up a named column in the header. Files are opened
+/// on first use and at most `max_open` stay open at once; when the limit is
+/// reached the least recently used file is closed and later reopened in
+/// append mode.
+///
+/// The name and keys are escaped to be safe as a single path segment, whatever
+/// the constructor. An empty key is written as `__empty__`, and a null key of
+/// [`by_column`](PartitionedWriter::by_column) as `__null__`.
+pub struct PartitionedWriter<K> {
+    dir: PathBuf,
+    name: String,
+    key_fn: KeyFn<K>,
+    header: Option<Vec<Option<String>>>,
+    max_open: usize,
+    scratch: Writer<io::Sink>,
+    flexible: bool,
+    // Set when `key_fn` returns escaped directory names already
+    escaped: bool,
+    open: HashMap<K, Partition>,
+    seen: HashSet<K>,
+    tick: u64,
+}
+
+impl<K: Display + Eq + Hash + Clone> PartitionedWriter<K> {
+    /// Partitions rows by `key_fn`, which receives the values of each row.
+    /// `name` is used for the `<name>=<key>` directory names.
+    pub fn new<P, F>(dir: P, name: &str, key_fn: F) -> PartitionedWriter<K>
+    where
+        P: AsRef<Path>,
+        F: Fn(&[Option<&str>]) -> K + Send + 'static {
+        PartitionedWriter {
+            dir: dir.as_ref().to_owned(),
+            name: name.to_owned(),
+            key_fn: Box::new(key_fn),
+            header: None,
+            max_open: 64,
+            scratch: Writer::from_writer_unbuffered(io::sink()),
+            flexible: true,
+            escaped: false,
+            open: HashMap::new(),
+            seen: HashSet::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl PartitionedWriter<String> {
+    /// Partitions rows by the value of the column called `column` in `header`.
+    /// The header is written as the first row of every partition file.
+    pub fn by_column<P, I, T>(dir: P, header: I, column: &str) -> Result<PartitionedWriter<String>, Error>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = T>,
+        T: Into<String> {
+        let header: Vec<String> = header.into_iter().map(Into::into).collect();
+        let index = header.iter().position(|h| h == column).ok_or_else(|| Error(ErrorKind::Serialize(
+            format!("Partition column {:?} is not in the header", column)
+        )))?;
+
+        // Escaped here so a null can be told apart from the text `__null__`
+        let key_fn = move |row: &[Option<&str>]| match row.get(index).copied().flatten() {
+            Some(v) => segment(v),
+            None => "__null__".to_owned(),
+        };
+
+        let mut wtr = PartitionedWriter::new(dir, column, key_fn).with_header(header);
+        wtr.escaped = true;
+        Ok(wtr)
+    }
+}
+
+impl<K: Display + Eq + Hash + Clone> PartitionedWriter<K> {
+    /// Writes `header` as the first row of every partition file.
+    pub fn with_header<I, T>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String> {
+        self.header = Some(header.into_iter().map(|h| Some(h.into())).collect());
+        self
+    }
+
+    /// Sets how many partition files may be open at once. Defaults to 64.
+    pub fn with_max_open(mut self, max_open: usize) -> Self {
+        self.max_open = max_open.max(1);
+        self
+    }
+
+    /// See [`Writer::with_byte_encoding`].
+    pub fn with_byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.scratch.opts.bytes = encoding;
+        self
+    }
+
+    /// See [`Writer::with_float_format`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.scratch.opts.floats = format;
+        self
+    }
+
+    /// See [`Writer::with_flexible`]. Checked separately for every partition.
+    pub fn with_flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    pub fn serialize<S: Serialize>(&mut self, record: S) -> Result<(), Error> {
+        let (row, fields) = self.scratch.stage(record)?;
+        let (row, fields) = (row.to_vec(), fields);
+
+        let key = {
+            let cells = split_cells(&row);
+            let cells: Vec<Option<&str>> = cells.iter().map(|c| c.as_deref()).collect();
+            (self.key_fn)(&cells)
+        };
+
+        self.partition(key)?.write_staged(&row, fields)
+    }
+
+    pub fn write_record<'r, I, T>(&mut self, rec: I) -> Result<(), Error>
+        where I: IntoIterator<Item = &'r Option<T>>, T: AsRef<[u8]> + 'r {
+        let rec: Vec<&Option<T>> = rec.into_iter().collect();
+
+        let key = {
+            let cells: Vec<Option<Cow<str>>> = rec.iter()
+                .map(|v| v.as_ref().map(|v| String::from_utf8_lossy(v.as_ref())))
+                .collect();
+            let cells: Vec<Option<&str>> = cells.iter().map(|c| c.as_deref()).collect();
+            (self.key_fn)(&cells)
+        };
+
+        self.partition(key)?.write_record(rec)
+    }
+
+    /// Flushes every open partition file.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        for p in self.open.values_mut() {
+            p.wtr.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Finishes every open partition file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        for (_, p) in self.open.drain() {
+            p.wtr.finish()?;
+        }
+        Ok(())
+    }
+
+    fn partition(&mut self, key: K) -> Result<&mut Writer<FileSink>, Error> {
+        self.tick += 1;
+
+        if !self.open.contains_key(&key) {
+            if self.open.len() >= self.max_open {
+                self.evict()?;
+            }
+            let wtr = self.open_partition(&key)?;
+            self.open.insert(key.clone(), Partition { wtr, last_used: 0 });
+        }
+
+        let p = self.open.get_mut(&key).unwrap();
+        p.last_used = self.tick;
+        Ok(&mut p.wtr)
+    }
+
+    fn open_partition(&mut self, key: &K) -> Result<Writer<FileSink>, Error> {
+        let value = match self.escaped {
+            true => key.to_string(),
+            false => segment(&key.to_string()),
+        };
+        let dir = format!("{}={}", segment(&self.name), value);
+        let path = self.dir.join(dir).join("part-0.rsv");
+
+        // Files are truncated the first time a key is seen and appended to
+        // when reopened after being evicted.
+        let mut wtr = if self.seen.contains(key) {
+            Writer::append_to_path(&path, TornRowPolicy::Error)?.with_flexible(self.flexible)
+        } else {
+            fs::create_dir_all(path.parent().unwrap())?;
+            let mut wtr = Writer::from_path(&path)?.with_flexible(self.flexible);
+            if let Some(header) = &self.header {
+                wtr.write_record(header)?;
+            }
+            self.seen.insert(key.clone());
+            wtr
+        };
+
+        wtr.opts = self.scratch.opts.clone();
+        Ok(wtr)
+    }
+
+    fn evict(&mut self) -> Result<(), Error> {
+        let oldest = self.open.iter()
+            .min_by_key(|(_, p)| p.last_used)
+            .map(|(k, _)| k.clone());
+
+        if let Some(p) = oldest.and_then(|k| self.open.remove(&k)) {
+            p.wtr.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits encoded values into cells, `None` for nulls.
+fn split_cells(row: &[u8]) -> Vec<Option<Cow<'_, str>>> {
+    let mut cells: Vec<_> = row.split(|&b| b == VALUE_TERM_BYTE)
+        .map(|v| match v {
+            [NULL_BYTE] => None,
+            v => Some(String::from_utf8_lossy(v)),
+        })
+        .collect();
+    // Every value is terminated, so the last split is always empty
+    cells.pop();
+    cells
+}
+
+/// Escapes a name or key for use in a `<name>=<key>` directory name.
+fn segment(s: &str) -> String {
+    match s {
+        "" => "__empty__".to_owned(),
+        s => escape(s),
+    }
+}
+
+/// Percent-encodes anything but ASCII alphanumerics, `-`, `_` and `.`. A
+/// leading `.` is encoded too so the key can never be `.`, `..` or a hidden
+/// file, and a leading `_` so it can't be mistaken for `__null__` or `__empty__`.
+pub(crate) fn escape(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    for (i, b) in key.bytes().enumerate() {
+        match b {
+            b'.' if i == 0 => out.push_str("%2E"),
+            b'_' if i == 0 => out.push_str("%5F"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => out.push(b as char),
+            _ => { let _ = write!(out, "%{:02X}", b); },
+        }
+    }
+    out
+}
@@ -0,0 +1,223 @@
+use std::{borrow::Cow, time::Duration};
+
+/// Controls how leniently scalar values (numbers, bools and options) are parsed.
+///
+/// The default policy is strict and only accepts the exact spellings produced
+/// by [`crate::writer::Writer`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParsePolicy {
+    /// Trim leading and trailing whitespace from numbers and bools.
+    pub trim: bool,
+    /// Accept `1`/`0`, `yes`/`no` and any capitalisation of `true`/`false` as bools.
+    pub lenient_bools: bool,
+    /// Deserialize empty values as `None` when the target is an `Option<T>`.
+    pub empty_as_none: bool,
+    /// Accept additional spellings of NaN and infinity, such as `-nan`, `∞`
+    /// or `1.#INF`, on top of those understood by `str::parse`.
+    pub special_floats: bool,
+    /// Ignore this character when parsing numbers, e.g. `Some(',')` for `1,000,000`.
+    pub thousands_separator: Option<char>,
+}
+
+impl ParsePolicy {
+    /// A policy that enables every option except the thousands separator,
+    /// which has to be chosen explicitly.
+    pub fn lenient() -> Self {
+        ParsePolicy {
+            trim: true,
+            lenient_bools: true,
+            empty_as_none: true,
+            special_floats: true,
+            thousands_separator: None,
+        }
+    }
+
+    pub(crate) fn trim<'a>(&self, value: &'a str) -> &'a str {
+        match self.trim {
+            true => value.trim(),
+            false => value,
+        }
+    }
+
+    pub(crate) fn parse_bool(&self, value: &str) -> Option<bool> {
+        let value = self.trim(value);
+        match value {
+            "true" => return Some(true),
+            "false" => return Some(false),
+            _ if !self.lenient_bools => return None,
+            _ => (),
+        }
+
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Some(true),
+            "false" | "no" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn number<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let value = self.trim(value);
+        match self.thousands_separator {
+            Some(sep) if value.contains(sep) => Cow::Owned(value.replace(sep, "")),
+            _ => Cow::Borrowed(value),
+        }
+    }
+
+    pub(crate) fn float<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let value = self.number(value);
+        if !self.special_floats {
+            return value;
+        }
+
+        let (sign, unsigned) = match value.strip_prefix('-') {
+            Some(v) => ("-", v),
+            None => ("", value.strip_prefix('+').unwrap_or(&value)),
+        };
+
+        match unsigned.to_ascii_lowercase().as_str() {
+            "nan" | "qnan" | "snan" | "1.#qnan" | "1.#snan" | "1.#ind" | "nan(ind)" => Cow::Borrowed("NaN"),
+            "∞" | "1.#inf" | "infinite" => Cow::Owned(format!("{}inf", sign)),
+            _ => value,
+        }
+    }
+}
+
+/// What to do when a row has fewer values than the struct or tuple it is
+/// deserialized into.
+///
+/// Fields marked `#[serde(default)]` are filled by serde in any case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingCells {
+    /// Fail with [`crate::error::ErrorKind::UnequalLengths`].
+    #[default]
+    Error,
+    /// Treat missing values as null, so `Option` fields become `None` and
+    /// other fields fail as they would for a null value.
+    Null,
+    /// Fill missing values with `None` for options and with zero, `false`,
+    /// an empty string or an empty sequence for everything else.
+    Default,
+}
+
+/// What to do when a row has more values than the struct or tuple it is
+/// deserialized into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExtraCells {
+    /// Skip the extra values.
+    #[default]
+    Ignore,
+    /// Fail with [`crate::error::ErrorKind::UnequalLengths`].
+    Reject,
+    /// Deserialize the last field from all remaining values, so it must be a
+    /// sequence such as `Vec<Option<String>>`. It is empty if there are none.
+    Collect,
+}
+
+/// How values that are not valid UTF-8 are read, e.g. to recover data from a
+/// partially corrupted file.
+///
+/// Every salvaged value is reported as a [`crate::diagnostics::Diagnostic`].
+/// A stray `NULL_BYTE` that is not followed by a value terminator is
+/// salvaged the same way, as part of the value it appears in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Fail the row.
+    #[default]
+    Strict,
+    /// Replace invalid sequences with U+FFFD.
+    Replace,
+    /// Pass the raw bytes to the visitor, which works for byte targets such
+    /// as `serde_bytes::ByteBuf` but fails for strings and numbers.
+    RawBytes,
+    /// Read the value as null, so `Option` fields become `None`.
+    Skip,
+}
+
+/// Upper bounds on the size of the input, to protect against files that are
+/// malformed or malicious, e.g. missing their row terminators.
+///
+/// Every limit is unset by default. A row that exceeds a limit fails with
+/// [`crate::error::ErrorKind::LimitExceeded`], and the rest of it is skipped
+/// without being buffered so reading can continue with the next row.
+/// Integrity trailers are exempt, see [`crate::trailer`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum bytes per row, including all terminators.
+    pub max_row_bytes: Option<usize>,
+    /// Maximum values per row.
+    pub max_fields: Option<usize>,
+    /// Maximum bytes per value, not including its terminator.
+    pub max_value_bytes: Option<usize>,
+}
+
+/// One of the [`Limits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    RowBytes,
+    Fields,
+    ValueBytes,
+}
+
+/// What to do when the input ends with a row that is missing its
+/// `ROW_TERM_BYTE`, e.g. because the file was truncated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FinalRowPolicy {
+    /// Fail with [`crate::error::ErrorKind::Truncated`], as the spec requires
+    /// every row to be terminated.
+    #[default]
+    Error,
+    /// Complete the missing terminators and return the row.
+    Accept,
+    /// Drop the row.
+    Ignore,
+}
+
+/// Where the input ended in an unterminated final row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Truncation {
+    /// In the middle of a value, which is missing its `VALUE_TERM_BYTE`.
+    MidValue,
+    /// After a complete value, with only the `ROW_TERM_BYTE` missing.
+    MidRow,
+}
+
+/// What to do when a file opened for appending ends with a partial row,
+/// for example after a crash in the middle of a write.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TornRowPolicy {
+    /// Refuse to open the file.
+    #[default]
+    Error,
+    /// Truncate the file back to the end of its last complete row.
+    Truncate,
+}
+
+/// How often a file writer forces written rows to disk with `fsync`.
+///
+/// Syncing is checked after each row is written, so [`Durability::Interval`]
+/// only syncs once a row arrives after the interval has passed. Use
+/// [`crate::writer::Writer::sync`] to sync at any other point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Durability {
+    /// Leave it to the operating system. Rows that were flushed survive a
+    /// crash of the process, but not necessarily of the machine.
+    #[default]
+    Never,
+    /// Sync after every row.
+    EveryRow,
+    /// Sync after every N rows.
+    EveryRows(u64),
+    /// Sync when a row is written at least this long after the last sync.
+    Interval(Duration),
+}
+
+impl Durability {
+    pub(crate) fn is_due(&self, unsynced: u64, since_sync: Duration) -> bool {
+        match *self {
+            Durability::Never => false,
+            Durability::EveryRow => unsynced > 0,
+            Durability::EveryRows(n) => unsynced >= n.max(1),
+            Durability::Interval(t) => unsynced > 0 && since_sync >= t,
+        }
+    }
+}
@@ -1,13 +1,41 @@
-use std::{fs::File, io::{BufRead, BufReader, Read}, marker::PhantomData, path::Path};
+use std::{io::{BufRead, BufReader, Read}, marker::PhantomData, path::Path};
 
 use serde::de::DeserializeOwned;
-use crate::deserializer::DeRecord;
+use crate::deserializer::{DeOptions, DeRecord};
+use crate::encoding::ByteEncoding;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Summary, EXCERPT_LEN};
+use crate::policy::{ExtraCells, FinalRowPolicy, Limit, Limits, MissingCells, ParsePolicy, Truncation, Utf8Policy};
+use crate::source::FileSource;
+use crate::trailer::{is_trailer, may_be_trailer, Segment};
+use crate::value::ValueInference;
 
 use crate::error::{ErrorKind, Error};
-use crate::utils::ROW_TERM_BYTE;
+use crate::utils::{ROW_TERM_BYTE, VALUE_TERM_BYTE};
 
 pub struct Reader<R: Read> {
     rdr: BufReader<R>,
+    opts: DeOptions,
+    flexible: bool,
+    // Field count of the first row, used to check every later row
+    columns: Option<usize>,
+    rows: u64,
+    // Rows since the last trailer, only tracked when verifying trailers
+    trailer: Option<Segment>,
+    trailers: u64,
+    diagnostics: Vec<Diagnostic>,
+    max_diagnostics: usize,
+    recovery: bool,
+    summary: Summary,
+    limits: Limits,
+    // Set after a row exceeded a limit, until the rest of it was skipped
+    skipping: bool,
+    // Bytes consumed from the input, and the offset of the last row read
+    offset: u64,
+    row_start: u64,
+    // Start of the last row that exceeded a limit, as it is not returned
+    excerpt: Vec<u8>,
+    final_row: FinalRowPolicy,
+    truncation: Option<Truncation>,
 }
 
 impl<R: Read> Reader<R> {
@@ -16,22 +44,127 @@ impl<R: Read> Reader<R> {
         let rdr = BufReader::new(rdr);
         Reader {
             rdr,
+            opts: DeOptions::default(),
+            flexible: true,
+            columns: None,
+            rows: 0,
+            trailer: None,
+            trailers: 0,
+            diagnostics: Vec::new(),
+            max_diagnostics: 1000,
+            recovery: false,
+            summary: Summary::default(),
+            limits: Limits::default(),
+            skipping: false,
+            offset: 0,
+            row_start: 0,
+            excerpt: Vec::new(),
+            final_row: FinalRowPolicy::default(),
+            truncation: None,
         }
     }
+
+    /// When disabled, every row must have as many fields as the first row
+    /// or reading it fails with [`ErrorKind::UnequalLengths`]. Enabled by default.
+    pub fn with_flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    /// Verifies the integrity trailers written by [`crate::writer::Writer::with_trailer`].
+    /// See [`crate::trailer`].
+    ///
+    /// Trailer rows are checked and skipped instead of being returned. Reading
+    /// fails if a trailer doesn't match the rows before it, or if the input
+    /// doesn't end with a trailer, e.g. because it was truncated.
+    pub fn with_verify_trailer(mut self, verify: bool) -> Self {
+        self.trailer = verify.then(Segment::new);
+        self
+    }
+
+    /// Sets how values are interpreted when the target type is self-describing,
+    /// such as [`crate::Value`] or `#[serde(untagged)]` enums.
+    pub fn with_value_inference(mut self, inference: ValueInference) -> Self {
+        self.opts.inference = inference;
+        self
+    }
+
+    /// Sets how byte fields are decoded. See [`ByteEncoding`].
+    pub fn with_byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.opts.bytes = encoding;
+        self
+    }
+
+    /// Sets how leniently numbers, bools and options are parsed. See [`ParsePolicy`].
+    pub fn with_parse_policy(mut self, policy: ParsePolicy) -> Self {
+        self.opts.policy = policy;
+        self
+    }
+
+    /// Sets what happens when the input ends with a row that is missing its
+    /// terminator. See [`FinalRowPolicy`].
+    pub fn with_final_row_policy(mut self, policy: FinalRowPolicy) -> Self {
+        self.final_row = policy;
+        self
+    }
+
+    /// When enabled, [`Reader::deserialize`] skips rows that fail to decode,
+    /// exceed a limit or have the wrong number of fields, and reports them as
+    /// [`Reader::diagnostics`] instead of returning an error. Errors reading
+    /// the input itself are still returned. Disabled by default.
+    pub fn with_error_recovery(mut self, recovery: bool) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
+    /// Sets how many diagnostics are kept. Later ones are only counted in
+    /// [`Reader::summary`]. Defaults to 1000.
+    pub fn with_max_diagnostics(mut self, max: usize) -> Self {
+        self.max_diagnostics = max;
+        self
+    }
+
+    /// Sets upper bounds on the size of rows and values. See [`Limits`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how values that are not valid UTF-8 are read. See [`Utf8Policy`].
+    /// Salvaged values are reported by [`Reader::diagnostics`].
+    pub fn with_utf8_policy(mut self, utf8: Utf8Policy) -> Self {
+        self.opts.utf8 = utf8;
+        self
+    }
+
+    /// Sets what happens when a row has fewer values than the target struct
+    /// or tuple. See [`MissingCells`].
+    pub fn with_missing_cells(mut self, missing: MissingCells) -> Self {
+        self.opts.missing = missing;
+        self
+    }
+
+    /// Sets what happens when a row has more values than the target struct
+    /// or tuple. See [`ExtraCells`].
+    pub fn with_extra_cells(mut self, extra: ExtraCells) -> Self {
+        self.opts.extra = extra;
+        self
+    }
 }
 
-impl Reader<File> {
-    /// Creates a new buffered reader from a file path.
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<File>, Error> {
-        let f = File::open(path)?;
+impl Reader<FileSource> {
+    /// Creates a new buffered reader from a file path. Compressed files are
+    /// detected and decompressed, see [`crate::compression`].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Reader<FileSource>, Error> {
+        let f = FileSource::open(path.as_ref())?;
 
         Ok(Reader::from_reader(f))
     }
 }
 
-impl<'a, R: Read> Reader<R> {
+impl<R: Read> Reader<R> {
     /// Returns an iterator that will continuously decode records from the underlying reader.
-    pub fn deserialize<'de, D: DeserializeOwned>(&mut self) -> DesRecordIter<'_, D, R> {
+    pub fn deserialize<D: DeserializeOwned>(&mut self) -> DesRecordIter<'_, D, R> {
 
         DesRecordIter::<D, R> {
             _priv: PhantomData,
@@ -39,19 +172,272 @@ impl<'a, R: Read> Reader<R> {
         }
     }
 
-    pub fn read_record(&mut self, mut buf: &mut Vec<u8>) -> Option<Result<(), Error>> {
-        match self.rdr.read_until(ROW_TERM_BYTE, &mut buf) {
-            Ok(0) => None,
-            Ok(_) => Some(Ok(())),
-            Err(_) => Some(Err(Error(ErrorKind::Deserialize(
-                "Failed to read record".to_owned()
-            )))),
+    /// Reads the raw bytes of the next row, including its `ROW_TERM_BYTE`, into `buf`.
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> Option<Result<(), Error>> {
+        let start = buf.len();
+
+        loop {
+            if self.skipping {
+                if let Err(e) = self.skip_row() {
+                    return Some(Err(e));
+                }
+            }
+
+            self.row_start = self.offset;
+            match self.read_row(buf) {
+                Ok(0) => return self.check_end(),
+                Ok(_) => (),
+                Err(e) => {
+                    if let (ErrorKind::LimitExceeded { .. }, Some(segment)) = (&e.0, &mut self.trailer) {
+                        segment.skip();
+                    }
+                    return Some(Err(e));
+                },
+            }
+
+            if buf.last() != Some(&ROW_TERM_BYTE) {
+                let truncation = match buf.last() {
+                    Some(&VALUE_TERM_BYTE) => Truncation::MidRow,
+                    _ => Truncation::MidValue,
+                };
+                self.truncation = Some(truncation);
+
+                match self.final_row {
+                    FinalRowPolicy::Error => {
+                        self.rows += 1;
+                        return Some(Err(Error(ErrorKind::Truncated { row: self.rows, truncation })));
+                    },
+                    FinalRowPolicy::Ignore => {
+                        buf.truncate(start);
+                        return self.check_end();
+                    },
+                    FinalRowPolicy::Accept => {
+                        if truncation == Truncation::MidValue {
+                            buf.push(VALUE_TERM_BYTE);
+                        }
+                        buf.push(ROW_TERM_BYTE);
+                    },
+                }
+            }
+
+            if let Some(segment) = &mut self.trailer {
+                let row = &buf[start..];
+                if is_trailer(row) {
+                    let res = segment.verify(row);
+                    *segment = Segment::new();
+                    self.trailers += 1;
+                    buf.truncate(start);
+                    match res {
+                        Ok(()) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                segment.update(row);
+            }
+
+            self.rows += 1;
+            return Some(self.check_columns(&buf[start..]));
+        }
+    }
+
+    /// Reads the next row into `buf` and returns its length, or 0 at the end
+    /// of the input. Stops as soon as a limit is exceeded.
+    fn read_row(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        if self.limits == Limits::default() {
+            let len = self.rdr.read_until(ROW_TERM_BYTE, buf).map_err(read_failed)?;
+            self.offset += len as u64;
+            return Ok(len);
+        }
+
+        let start = buf.len();
+        let (mut fields, mut value_len) = (0, 0);
+
+        loop {
+            let chunk = self.rdr.fill_buf().map_err(read_failed)?;
+            if chunk.is_empty() {
+                return Ok(buf.len() - start);
+            }
+
+            let (len, done) = match chunk.iter().position(|&b| b == ROW_TERM_BYTE) {
+                Some(i) => (i + 1, true),
+                None => (chunk.len(), false),
+            };
+
+            let mut exceeded = None;
+            for &b in &chunk[..len] {
+                match b {
+                    VALUE_TERM_BYTE => {
+                        fields += 1;
+                        value_len = 0;
+                    },
+                    ROW_TERM_BYTE => (),
+                    _ => value_len += 1,
+                }
+                exceeded = exceeded
+                    .or(check(Limit::Fields, self.limits.max_fields, fields))
+                    .or(check(Limit::ValueBytes, self.limits.max_value_bytes, value_len));
+            }
+            exceeded = exceeded.or(check(Limit::RowBytes, self.limits.max_row_bytes, buf.len() - start + len));
+
+            // Trailer rows are exempt, so they must be read in full to tell
+            if exceeded.is_some() && self.trailer.is_some() {
+                let row: Vec<u8> = buf[start..].iter().chain(&chunk[..len]).copied().collect();
+                if may_be_trailer(&row) {
+                    exceeded = None;
+                }
+            }
+
+            if let Some((limit, max)) = exceeded {
+                self.excerpt = buf[start..].iter().chain(&chunk[..len]).take(EXCERPT_LEN).copied().collect();
+                self.rdr.consume(len);
+                self.offset += len as u64;
+                buf.truncate(start);
+                self.skipping = !done;
+                self.rows += 1;
+                return Err(Error(ErrorKind::LimitExceeded { row: self.rows, limit, max }));
+            }
+
+            buf.extend_from_slice(&chunk[..len]);
+            self.rdr.consume(len);
+            self.offset += len as u64;
+            if done {
+                return Ok(buf.len() - start);
+            }
+        }
+    }
+
+    /// Discards the rest of a row that exceeded a limit.
+    fn skip_row(&mut self) -> Result<(), Error> {
+        loop {
+            let chunk = self.rdr.fill_buf().map_err(read_failed)?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            match chunk.iter().position(|&b| b == ROW_TERM_BYTE) {
+                Some(i) => {
+                    self.rdr.consume(i + 1);
+                    self.offset += i as u64 + 1;
+                    break;
+                },
+                None => {
+                    let len = chunk.len();
+                    self.rdr.consume(len);
+                    self.offset += len as u64;
+                },
+            }
+        }
+
+        self.skipping = false;
+        Ok(())
+    }
+
+    /// Reports rows at the end of the input that are not covered by a trailer.
+    fn check_end(&mut self) -> Option<Result<(), Error>> {
+        // Taken so the error is only reported once
+        let segment = self.trailer.take()?;
+        if segment.is_empty() && self.trailers > 0 {
+            return None;
+        }
+
+        Some(Err(Error(ErrorKind::Deserialize(
+            "Integrity check failed: input does not end with a trailer".to_owned()
+        ))))
+    }
+
+    /// The number of rows read so far.
+    pub fn position(&self) -> u64 {
+        self.rows
+    }
+
+    /// Where the input ended, if its final row was missing its terminator.
+    /// Set whatever the [`FinalRowPolicy`].
+    pub fn truncation(&self) -> Option<Truncation> {
+        self.truncation
+    }
+
+    /// Problems that were recovered from while deserializing rows so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Counts of rows read and skipped and of problems found so far.
+    pub fn summary(&self) -> Summary {
+        Summary { rows_read: self.rows, ..self.summary }
+    }
+
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        self.rdr.get_mut()
+    }
+
+    pub(crate) fn has_buffered(&self) -> bool {
+        !self.rdr.buffer().is_empty()
+    }
+
+    pub(crate) fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.summary.diagnostics += 1;
+        match self.diagnostics.len() < self.max_diagnostics {
+            true => self.diagnostics.push(diagnostic),
+            false => self.summary.diagnostics_dropped += 1,
         }
     }
 
+    /// Reports a row that is skipped because of `err`, or returns `err` if it
+    /// can't be recovered from.
+    fn skip_failed_row(&mut self, err: Error, field: Option<usize>, row: &[u8]) -> Result<(), Error> {
+        let kind = match err.0 {
+            ErrorKind::LimitExceeded { limit, .. } => DiagnosticKind::LimitExceeded(limit),
+            ErrorKind::UnequalLengths { expected, actual, .. } => DiagnosticKind::UnequalLengths { expected, actual },
+            ErrorKind::Truncated { truncation, .. } => DiagnosticKind::Truncated(truncation),
+            ErrorKind::Deserialize(msg) if field.is_some() => DiagnosticKind::Malformed(msg),
+            _ => return Err(err),
+        };
+        let excerpt = match kind {
+            DiagnosticKind::LimitExceeded(_) => std::mem::take(&mut self.excerpt),
+            _ => row[..row.len().min(EXCERPT_LEN)].to_vec(),
+        };
+
+        self.summary.rows_skipped += 1;
+        self.report(Diagnostic { row: self.rows, byte_offset: self.row_start, field, kind, raw_excerpt: excerpt });
+        Ok(())
+    }
+
+    fn check_columns(&mut self, row: &[u8]) -> Result<(), Error> {
+        if self.flexible {
+            return Ok(());
+        }
 
+        let actual = row.iter().filter(|&&b| b == VALUE_TERM_BYTE).count();
+        match self.columns {
+            None => self.columns = Some(actual),
+            Some(expected) if expected != actual => {
+                return Err(Error(ErrorKind::UnequalLengths { row: self.rows, expected, actual }));
+            },
+            Some(_) => (),
+        }
+
+        Ok(())
+    }
+}
+
+fn read_failed(_: std::io::Error) -> Error {
+    Error(ErrorKind::Deserialize("Failed to read record".to_owned()))
 }
 
+fn check(limit: Limit, max: Option<usize>, actual: usize) -> Option<(Limit, usize)> {
+    match max {
+        Some(max) if actual > max => Some((limit, max)),
+        _ => None,
+    }
+}
 
 pub struct DesRecordIter<'a, D: DeserializeOwned, R: Read> {
     rdr: &'a mut Reader<R>,
@@ -62,15 +448,32 @@ impl<D: DeserializeOwned, R: Read> Iterator for DesRecordIter<'_, D, R> {
     type Item = Result<D, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut buf = Vec::new();
+        loop {
+            let mut buf = Vec::new();
 
-        match self.rdr.read_record(&mut buf) {
-            None => None,
-            Some(Ok(())) => {
-                let mut d = DeRecord::from_ref(&buf);
-                Some(D::deserialize(&mut d))
-            },
-            Some(Err(e)) => Some(Err(e))
+            let res = match self.rdr.read_record(&mut buf)? {
+                Ok(()) => {
+                    let mut d = DeRecord::from_ref(&buf, self.rdr.opts.clone());
+                    let res = D::deserialize(&mut d);
+                    for mut diagnostic in d.take_diagnostics() {
+                        diagnostic.row = self.rdr.rows;
+                        diagnostic.byte_offset += self.rdr.row_start;
+                        self.rdr.report(diagnostic);
+                    }
+                    res.map_err(|e| (e.at_row(self.rdr.rows), Some(d.current_field())))
+                },
+                Err(e) => Err((e, None)),
+            };
+
+            match res {
+                Ok(record) => return Some(Ok(record)),
+                Err((e, field)) if self.rdr.recovery => {
+                    if let Err(e) = self.rdr.skip_failed_row(e, field, &buf) {
+                        return Some(Err(e));
+                    }
+                },
+                Err((e, _)) => return Some(Err(e)),
+            }
         }
     }
 }
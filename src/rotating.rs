@@ -0,0 +1,211 @@
+use std::{fs, io, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use serde::Serialize;
+
+use crate::encoding::ByteEncoding;
+use crate::error::Error;
+use crate::format::FloatFormat;
+use crate::serializer::SerOptions;
+use crate::sink::FileSink;
+use crate::writer::Writer;
+
+type CompleteHook = Box<dyn FnMut(&Path) -> Result<(), Error> + Send>;
+
+struct Current {
+    wtr: Writer<FileSink>,
+    path: PathBuf,
+    opened: Instant,
+}
+
+/// Writes rows into a series of files, starting a new file whenever a size,
+/// row count or age limit is reached.
+///
+/// File names come from a template in which `{index}` is replaced by the
+/// number of the file, starting at 0, and `{timestamp}` by the Unix time in
+/// seconds when it was opened, e.g. `"export/part-{index}.rsv"`.
+///
+/// Every file is written with [`Writer::create_atomic`], so it only appears
+/// once complete. Files are opened lazily on the first row, so no empty files
+/// are produced. A file is never replaced: if the template yields a path that
+/// already exists, for example `{timestamp}` without `{index}` when rotating
+/// twice in one second, the write fails instead. Dropping the writer without
+/// calling [`RotatingWriter::finish`] discards the file currently being
+/// written.
+pub struct RotatingWriter {
+    template: String,
+    max_bytes: Option<u64>,
+    max_rows: Option<u64>,
+    max_age: Option<Duration>,
+    header: Option<Vec<Option<String>>>,
+    on_complete: Option<CompleteHook>,
+    opts: SerOptions,
+    flexible: bool,
+    index: u64,
+    current: Option<Current>,
+}
+
+impl RotatingWriter {
+    pub fn new<S: Into<String>>(template: S) -> RotatingWriter {
+        RotatingWriter {
+            template: template.into(),
+            max_bytes: None,
+            max_rows: None,
+            max_age: None,
+            header: None,
+            on_complete: None,
+            opts: SerOptions::default(),
+            flexible: true,
+            index: 0,
+            current: None,
+        }
+    }
+
+    /// Starts a new file once the current one holds at least `bytes` bytes.
+    /// A file may exceed the limit by up to one row.
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Starts a new file once the current one holds `rows` rows, not counting the header.
+    pub fn with_max_rows(mut self, rows: u64) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Starts a new file for the first row written after the current one has
+    /// been open for `age`.
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Writes `header` as the first row of every file.
+    pub fn with_header<I, T>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String> {
+        self.header = Some(header.into_iter().map(|h| Some(h.into())).collect());
+        self
+    }
+
+    /// Calls `hook` with the final path of each file once it is complete,
+    /// for example to upload or compress it. An error from the hook is
+    /// returned from the write that triggered the rotation.
+    pub fn on_file_complete<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(&Path) -> Result<(), Error> + Send + 'static {
+        self.on_complete = Some(Box::new(hook));
+        self
+    }
+
+    /// See [`Writer::with_byte_encoding`].
+    pub fn with_byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.opts.bytes = encoding;
+        self
+    }
+
+    /// See [`Writer::with_float_format`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.opts.floats = format;
+        self
+    }
+
+    /// See [`Writer::with_flexible`]. The header, if any, counts as the first row.
+    pub fn with_flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    pub fn serialize<S: Serialize>(&mut self, record: S) -> Result<(), Error> {
+        self.writer()?.serialize(record)?;
+        self.after_row()
+    }
+
+    pub fn write_record<'r, I, T>(&mut self, rec: I) -> Result<(), Error>
+        where I: IntoIterator<Item = &'r Option<T>>, T: AsRef<[u8]> + 'r {
+        self.writer()?.write_record(rec)?;
+        self.after_row()
+    }
+
+    /// Flushes the file currently being written.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        match &mut self.current {
+            Some(current) => current.wtr.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Finishes the file currently being written, if any.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.rotate()
+    }
+
+    fn writer(&mut self) -> Result<&mut Writer<FileSink>, Error> {
+        let expired = match (&self.current, self.max_age) {
+            (Some(current), Some(age)) => current.opened.elapsed() >= age,
+            _ => false,
+        };
+        if expired {
+            self.rotate()?;
+        }
+
+        if self.current.is_none() {
+            self.current = Some(self.open()?);
+        }
+
+        Ok(&mut self.current.as_mut().unwrap().wtr)
+    }
+
+    fn open(&mut self) -> Result<Current, Error> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let path = PathBuf::from(self.template
+            .replace("{index}", &self.index.to_string())
+            .replace("{timestamp}", &timestamp.to_string()));
+
+        if path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Rotating onto {} would replace an existing file", path.display()),
+            ).into());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut wtr = Writer::create_atomic(&path)?.with_flexible(self.flexible);
+        wtr.opts = self.opts.clone();
+        if let Some(header) = &self.header {
+            wtr.write_record(header)?;
+        }
+
+        self.index += 1;
+        Ok(Current { wtr, path, opened: Instant::now() })
+    }
+
+    fn after_row(&mut self) -> Result<(), Error> {
+        let current = self.current.as_ref().unwrap();
+        let rows = current.wtr.rows_written() - self.header.is_some() as u64;
+
+        let full = self.max_rows.is_some_and(|max| rows >= max)
+            || self.max_bytes.is_some_and(|max| current.wtr.bytes_written() >= max);
+        match full {
+            true => self.rotate(),
+            false => Ok(()),
+        }
+    }
+
+    fn rotate(&mut self) -> Result<(), Error> {
+        let current = match self.current.take() {
+            Some(current) => current,
+            None => return Ok(()),
+        };
+
+        current.wtr.finish()?;
+        if let Some(hook) = &mut self.on_complete {
+            hook(&current.path)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+//! Adapters for `chrono` types. Requires the `chrono` feature.
+//!
+//! Custom formats are created with [`crate::chrono_format!`].
+
+/// `DateTime<Utc>` as an RFC 3339 string, e.g. `2024-01-31T12:00:00.5+00:00`.
+/// Any offset is accepted when reading and converted to UTC.
+pub mod rfc3339 {
+    use ::chrono::{DateTime, Utc};
+
+    crate::__string_cell!(DateTime<Utc>, |v| Ok(v.to_rfc3339()), |s| {
+        DateTime::parse_from_rfc3339(s)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| format!("{}", e))
+    });
+}
+
+/// `NaiveDate` as `%Y-%m-%d`.
+pub mod naive_date {
+    use ::chrono::NaiveDate;
+
+    crate::__string_cell!(NaiveDate, |v| Ok(v.format("%Y-%m-%d").to_string()), |s| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("{}", e))
+    });
+}
+
+/// `NaiveDateTime` as `%Y-%m-%dT%H:%M:%S%.f`, with fractional seconds only when present.
+pub mod naive_datetime {
+    use ::chrono::NaiveDateTime;
+
+    crate::__string_cell!(NaiveDateTime, |v| Ok(v.format("%Y-%m-%dT%H:%M:%S%.f").to_string()), |s| {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").map_err(|e| format!("{}", e))
+    });
+}
+
+macro_rules! epoch {
+    ($(#[$doc:meta])* $name:ident, $to:ident, $from:ident) => {
+        $(#[$doc])*
+        pub mod $name {
+            use ::chrono::{DateTime, Utc};
+
+            crate::__string_cell!(DateTime<Utc>, |v| Ok(v.$to().to_string()), |s| {
+                let ts = s.parse::<i64>().map_err(|e| format!("{}", e))?;
+                epoch!(@from $from, ts).ok_or_else(|| format!("Timestamp {} is out of range", ts))
+            });
+        }
+    };
+    (@from from_timestamp, $ts:ident) => { DateTime::from_timestamp($ts, 0) };
+    (@from $from:ident, $ts:ident) => { DateTime::$from($ts) };
+}
+
+epoch!(
+    /// `DateTime<Utc>` as whole seconds since the Unix epoch.
+    ts_seconds, timestamp, from_timestamp
+);
+epoch!(
+    /// `DateTime<Utc>` as milliseconds since the Unix epoch.
+    ts_milliseconds, timestamp_millis, from_timestamp_millis
+);
+epoch!(
+    /// `DateTime<Utc>` as microseconds since the Unix epoch.
+    ts_microseconds, timestamp_micros, from_timestamp_micros
+);
+
+/// `DateTime<Utc>` as nanoseconds since the Unix epoch.
+pub mod ts_nanoseconds {
+    use ::chrono::{DateTime, Utc};
+
+    crate::__string_cell!(DateTime<Utc>, |v| {
+        v.timestamp_nanos_opt()
+            .map(|ts| ts.to_string())
+            .ok_or_else(|| format!("{} cannot be represented in nanoseconds", v))
+    }, |s| {
+        s.parse::<i64>().map(DateTime::from_timestamp_nanos).map_err(|e| format!("{}", e))
+    });
+}
+
+/// Defines a `#[serde(with = "...")]` module for a `chrono` type using a
+/// custom `strftime` format string.
+///
+/// Works with any type that has both `format` and `parse_from_str`, such as
+/// `NaiveDate`, `NaiveTime`, `NaiveDateTime` and `DateTime<FixedOffset>`.
+///
+/// ```ignore
+/// rsv_core::chrono_format!(pub mod us_date, NaiveDate, "%m/%d/%Y");
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Row {
+///     #[serde(with = "us_date")]
+///     day: NaiveDate,
+/// }
+/// ```
+#[macro_export]
+macro_rules! chrono_format {
+    ($vis:vis mod $name:ident, $ty:ty, $fmt:expr) => {
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::__string_cell!($ty, |v| Ok(v.format($fmt).to_string()), |s| {
+                <$ty>::parse_from_str(s, $fmt).map_err(|e| format!("{}", e))
+            });
+        }
+    };
+}
@@ -0,0 +1,14 @@
+//! Adapters for `rust_decimal::Decimal`. Requires the `rust_decimal` feature.
+
+/// Plain decimal notation that keeps the scale, e.g. `12.50`.
+/// Reading also accepts scientific notation such as `1.25e1`.
+pub mod plain {
+    use ::rust_decimal::Decimal;
+
+    crate::__string_cell!(Decimal, |v| Ok(v.to_string()), |s| {
+        match s.contains(['e', 'E']) {
+            true => Decimal::from_scientific(s),
+            false => Decimal::from_str_exact(s),
+        }.map_err(|e| format!("{}", e))
+    });
+}
@@ -0,0 +1,155 @@
+//! Adapters for use with `#[serde(with = "...")]` on record fields.
+//!
+//! Every adapter stores its value as a single string cell, so they behave the
+//! same no matter which [`crate::ByteEncoding`] or [`crate::ParsePolicy`] is
+//! in use. Each module also has an `option` submodule for `Option<T>` fields,
+//! where `None` is written as the RSV null value.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Event {
+//!     #[serde(with = "rsv_core::serde_helpers::chrono::ts_milliseconds")]
+//!     at: DateTime<Utc>,
+//!     #[serde(with = "rsv_core::serde_helpers::bool_int::option")]
+//!     active: Option<bool>,
+//! }
+//! ```
+//!
+//! Helpers for third-party types sit behind a cargo feature of the same name:
+//! `chrono`, `time`, `uuid` and `rust_decimal`.
+
+#[cfg(feature = "chrono")]
+pub mod chrono;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
+
+/// Generates `serialize`/`deserialize` functions, plus an `option` submodule,
+/// that store a value as one string cell.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __string_cell {
+    ($ty:ty, |$v:ident| $to:expr, |$s:ident| $from:expr) => {
+        pub fn serialize<S>(value: &$ty, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where
+            S: $crate::__private::serde::Serializer {
+            #[allow(clippy::redundant_closure_call)]
+            let cell: ::std::result::Result<::std::string::String, ::std::string::String> = (|$v: &$ty| $to)(value);
+            match cell {
+                Ok(cell) => serializer.serialize_str(&cell),
+                Err(e) => Err(<S::Error as $crate::__private::serde::ser::Error>::custom(e)),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> ::std::result::Result<$ty, D::Error>
+        where
+            D: $crate::__private::serde::Deserializer<'de> {
+            let cell: ::std::string::String = $crate::__private::serde::Deserialize::deserialize(deserializer)?;
+            parse(&cell).map_err(<D::Error as $crate::__private::serde::de::Error>::custom)
+        }
+
+        fn parse($s: &str) -> ::std::result::Result<$ty, ::std::string::String> {
+            $from
+        }
+
+        pub mod option {
+            #[allow(unused_imports)]
+            use super::*;
+
+            pub fn serialize<S>(value: &Option<$ty>, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::__private::serde::Serializer {
+                match value {
+                    Some(v) => super::serialize(v, serializer),
+                    None => serializer.serialize_none(),
+                }
+            }
+
+            pub fn deserialize<'de, D>(deserializer: D) -> ::std::result::Result<Option<$ty>, D::Error>
+            where
+                D: $crate::__private::serde::Deserializer<'de> {
+                let cell: Option<::std::string::String> = $crate::__private::serde::Deserialize::deserialize(deserializer)?;
+                cell.map(|c| super::parse(&c))
+                    .transpose()
+                    .map_err(<D::Error as $crate::__private::serde::de::Error>::custom)
+            }
+        }
+    };
+}
+
+/// Stores any `T: Display + FromStr` as its string form. Useful for
+/// `IpAddr`, `SocketAddr` and other types with a canonical text format.
+pub mod display_fromstr {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer {
+        serializer.collect_str(value).map_err(|e| S::Error::custom(e.to_string()))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de> {
+        let cell = String::deserialize(deserializer)?;
+        cell.parse().map_err(D::Error::custom)
+    }
+}
+
+/// IP addresses in their standard text form, e.g. `192.168.0.1` or `::1`.
+pub mod ip {
+    use std::net::IpAddr;
+
+    __string_cell!(IpAddr, |v| Ok(v.to_string()), |s| s.parse().map_err(|e| format!("{}", e)));
+}
+
+/// Bools stored as `1` and `0`.
+pub mod bool_int {
+    __string_cell!(bool, |v| Ok(if *v { "1" } else { "0" }.to_owned()), |s| match s {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        _ => Err(format!("Expected 1 or 0 for bool, got {:?}", s)),
+    });
+}
+
+/// `std::time::Duration` stored as an integer count of a fixed unit.
+///
+/// Serializing truncates anything smaller than the unit, use `nanos` for
+/// lossless output.
+pub mod duration {
+    macro_rules! unit {
+        ($name:ident, $as:ident, $from:ident, $int:ty) => {
+            pub mod $name {
+                use std::time::Duration;
+
+                __string_cell!(Duration, |v| Ok(v.$as().to_string()), |s| {
+                    s.parse::<$int>().map(Duration::$from).map_err(|e| format!("{}", e))
+                });
+            }
+        };
+    }
+
+    unit!(secs, as_secs, from_secs, u64);
+    unit!(millis, as_millis, from_millis, u64);
+    unit!(micros, as_micros, from_micros, u64);
+
+    pub mod nanos {
+        use std::time::Duration;
+
+        __string_cell!(Duration, |v| Ok(v.as_nanos().to_string()), |s| {
+            let nanos = s.parse::<u128>().map_err(|e| format!("{}", e))?;
+            let secs = u64::try_from(nanos / 1_000_000_000)
+                .map_err(|_| format!("Duration of {} nanoseconds is out of range", nanos))?;
+            Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+        });
+    }
+}
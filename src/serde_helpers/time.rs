@@ -0,0 +1,81 @@
+//! Adapters for `time` types. Requires the `time` feature.
+//!
+//! Custom formats are created with [`crate::time_format!`].
+
+/// `OffsetDateTime` as an RFC 3339 string, e.g. `2024-01-31T12:00:00.5Z`.
+pub mod rfc3339 {
+    use ::time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+    crate::__string_cell!(OffsetDateTime, |v| v.format(&Rfc3339).map_err(|e| format!("{}", e)), |s| {
+        OffsetDateTime::parse(s, &Rfc3339).map_err(|e| format!("{}", e))
+    });
+}
+
+/// `Date` as an ISO 8601 calendar date, e.g. `2024-01-31`.
+pub mod date {
+    use ::time::{macros::format_description, Date};
+
+    crate::__string_cell!(Date, |v| v.format(format_description!("[year]-[month]-[day]")).map_err(|e| format!("{}", e)), |s| {
+        Date::parse(s, format_description!("[year]-[month]-[day]")).map_err(|e| format!("{}", e))
+    });
+}
+
+/// `OffsetDateTime` as whole seconds since the Unix epoch, read back as UTC.
+pub mod unix_timestamp {
+    use ::time::OffsetDateTime;
+
+    crate::__string_cell!(OffsetDateTime, |v| Ok(v.unix_timestamp().to_string()), |s| {
+        let ts = s.parse::<i64>().map_err(|e| format!("{}", e))?;
+        OffsetDateTime::from_unix_timestamp(ts).map_err(|e| format!("{}", e))
+    });
+}
+
+/// `OffsetDateTime` as milliseconds since the Unix epoch, read back as UTC.
+pub mod unix_timestamp_millis {
+    use ::time::OffsetDateTime;
+
+    crate::__string_cell!(OffsetDateTime, |v| Ok((v.unix_timestamp_nanos() / 1_000_000).to_string()), |s| {
+        let ts = s.parse::<i128>().map_err(|e| format!("{}", e))?;
+        let nanos = ts.checked_mul(1_000_000).ok_or_else(|| format!("{} milliseconds is out of range", ts))?;
+        OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|e| format!("{}", e))
+    });
+}
+
+/// `OffsetDateTime` as nanoseconds since the Unix epoch, read back as UTC.
+pub mod unix_timestamp_nanos {
+    use ::time::OffsetDateTime;
+
+    crate::__string_cell!(OffsetDateTime, |v| Ok(v.unix_timestamp_nanos().to_string()), |s| {
+        let ts = s.parse::<i128>().map_err(|e| format!("{}", e))?;
+        OffsetDateTime::from_unix_timestamp_nanos(ts).map_err(|e| format!("{}", e))
+    });
+}
+
+/// Defines a `#[serde(with = "...")]` module for a `time` type using a
+/// version 2 format description string.
+///
+/// Works with `Date`, `Time`, `PrimitiveDateTime` and `OffsetDateTime`.
+///
+/// ```ignore
+/// rsv_core::time_format!(pub mod us_date, Date, "[month]/[day]/[year]");
+/// ```
+#[macro_export]
+macro_rules! time_format {
+    ($vis:vis mod $name:ident, $ty:ty, $fmt:expr) => {
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            fn description() -> ::std::result::Result<
+                ::std::vec::Vec<$crate::__private::time::format_description::BorrowedFormatItem<'static>>,
+                ::std::string::String,
+            > {
+                $crate::__private::time::format_description::parse_borrowed::<2>($fmt).map_err(|e| format!("{}", e))
+            }
+
+            $crate::__string_cell!($ty, |v| v.format(&description()?).map_err(|e| format!("{}", e)), |s| {
+                <$ty>::parse(s, &description()?).map_err(|e| format!("{}", e))
+            });
+        }
+    };
+}
@@ -0,0 +1,21 @@
+//! Adapters for `uuid::Uuid`. Requires the `uuid` feature.
+//!
+//! Reading accepts any of the formats understood by `Uuid::parse_str`.
+
+/// Hyphenated lowercase form, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+pub mod hyphenated {
+    use ::uuid::Uuid;
+
+    crate::__string_cell!(Uuid, |v| Ok(v.hyphenated().to_string()), |s| {
+        Uuid::parse_str(s).map_err(|e| format!("{}", e))
+    });
+}
+
+/// Simple lowercase form without hyphens, e.g. `67e5504410b1426f9247bb680e5fe0c8`.
+pub mod simple {
+    use ::uuid::Uuid;
+
+    crate::__string_cell!(Uuid, |v| Ok(v.simple().to_string()), |s| {
+        Uuid::parse_str(s).map_err(|e| format!("{}", e))
+    });
+}
@@ -2,8 +2,14 @@ use std::io::Write;
 
 use serde::{ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant}, Serialize, Serializer};
 
-use crate::{error::Error, writer::Writer};
+use crate::{encoding::ByteEncoding, error::Error, format::FloatFormat, writer::Writer};
 
+/// Writer settings that affect how individual values are encoded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SerOptions {
+    pub(crate) bytes: ByteEncoding,
+    pub(crate) floats: FloatFormat,
+}
 
 pub(crate) struct SerRecord<'r, W: Write> {
     pub(crate) wtr: &'r mut Writer<W>
@@ -13,9 +19,9 @@ impl<'r, W: Write> SerializeSeq for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         value.serialize(&mut **self)
     }
 
@@ -28,9 +34,9 @@ impl<'r, W: Write> SerializeTuple for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         value.serialize(&mut **self)
     }
 
@@ -43,9 +49,9 @@ impl<'r, W: Write> SerializeTupleStruct for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         value.serialize(&mut **self)
     }
 
@@ -58,9 +64,9 @@ impl<'r, W: Write> SerializeTupleVariant for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         unimplemented!()
     }
 
@@ -96,9 +102,9 @@ impl<'r, W: Write> SerializeStruct for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         value.serialize(&mut **self)
     }
 
@@ -111,9 +117,9 @@ impl<'r, W: Write> SerializeStructVariant for &mut SerRecord<'r, W> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized + Serialize>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         unimplemented!()
     }
 
@@ -157,6 +163,10 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
         self.wtr.write_value(v.to_string())
     }
 
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        self.wtr.write_value(v.to_string())
+    }
+
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
         self.wtr.write_value(v.to_string())
     }
@@ -173,16 +183,22 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
         self.wtr.write_value(v.to_string())
     }
 
-    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
         self.wtr.write_value(v.to_string())
     }
 
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        let value = self.wtr.opts.floats.format(v)?;
+        self.wtr.write_value(value)
+    }
+
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_value(v.to_string())
+        let value = self.wtr.opts.floats.format(v)?;
+        self.wtr.write_value(value)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_value([v as u8])
+        self.wtr.write_value(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
@@ -190,16 +206,17 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_value(v)
+        let value = self.wtr.opts.bytes.encode(v)?;
+        self.wtr.write_value(value)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         self.wtr.write_null()
     }
 
-    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         value.serialize(self)
     }
 
@@ -220,17 +237,17 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
         unimplemented!()
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
             unimplemented!()
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -238,7 +255,7 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
         _value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: serde::Serialize {
+        T: ?Sized + serde::Serialize {
         unimplemented!()
     }
 
@@ -290,3 +307,31 @@ impl<'r, W: Write> Serializer for &mut SerRecord<'r, W> {
         unimplemented!()
     }
 }
+
+/// Serializes each item as one row into `wtr`, flushing it once done.
+pub fn to_writer<W, I>(wtr: W, records: I) -> Result<(), Error>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Serialize {
+    let mut wtr = Writer::from_writer_unbuffered(wtr);
+    for record in records {
+        wtr.serialize(record)?;
+    }
+
+    wtr.flush()
+}
+
+/// Serializes each item as one row into a new byte vector.
+///
+/// There is no `to_string`: an RSV document is never valid UTF-8, since every
+/// value and row is terminated by one of the bytes `0xFD` to `0xFF`.
+pub fn to_vec<I>(records: I) -> Result<Vec<u8>, Error>
+where
+    I: IntoIterator,
+    I::Item: Serialize {
+    let mut buf = Vec::new();
+    to_writer(&mut buf, records)?;
+
+    Ok(buf)
+}
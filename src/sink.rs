@@ -0,0 +1,201 @@
+use std::{fs::{self, File, OpenOptions}, io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, process, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::compression::{Compression, Encoder};
+use crate::error::{Error, ErrorKind};
+use crate::policy::TornRowPolicy;
+use crate::utils::{ROW_TERM_BYTE, VALUE_TERM_BYTE};
+
+/// A buffered file used by the path based [`crate::writer::Writer`] constructors.
+///
+/// Besides buffering, it knows how to finish the file, e.g. by ending the
+/// compressed stream or renaming a temporary file over its target, which a
+/// plain `File` cannot do. Files are compressed based on their extension,
+/// see [`crate::compression`].
+pub struct FileSink {
+    file: Encoder,
+    // Set while writing to a temporary file that replaces `target` on finish
+    atomic: Option<(PathBuf, PathBuf)>,
+    // A newly created file whose directory entry has not been synced yet
+    created: Option<PathBuf>,
+    // Compressors must not be written to or flushed after finishing
+    finished: bool,
+}
+
+impl FileSink {
+    /// Creates or truncates the file at `path`.
+    pub(crate) fn create(path: &Path) -> io::Result<FileSink> {
+        Ok(FileSink {
+            file: Encoder::new(File::create(path)?, Compression::from_extension(path).enabled())?,
+            atomic: None,
+            created: Some(path.to_owned()),
+            finished: false,
+        })
+    }
+
+    /// Creates a temporary file next to `path` that is renamed over it by `finish`.
+    pub(crate) fn create_atomic(path: &Path) -> io::Result<FileSink> {
+        let name = path.file_name().ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput, "Atomic writes need a path to a file"
+        ))?;
+
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let mut tmp_name = name.to_owned();
+        tmp_name.push(format!(".{}-{}.tmp", process::id(), nanos));
+        let tmp = path.with_file_name(tmp_name);
+
+        let f = OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+        let file = match Encoder::new(f, Compression::from_extension(path).enabled()) {
+            Ok(file) => file,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp);
+                return Err(e);
+            },
+        };
+
+        Ok(FileSink {
+            file,
+            atomic: Some((tmp, path.to_owned())),
+            // The directory is synced after the rename instead
+            created: None,
+            finished: false,
+        })
+    }
+
+    /// Opens `path` for appending, creating it if needed, after checking that it
+    /// ends with a complete row. Also returns the field count of the first row.
+    pub(crate) fn append(path: &Path, torn: TornRowPolicy) -> Result<(FileSink, Option<usize>), Error> {
+        if Compression::from_extension(path).enabled() != Compression::None {
+            return Err(Error(ErrorKind::IO("Appending to compressed files is not supported".to_owned())));
+        }
+        let created = match path.try_exists()? {
+            true => None,
+            false => Some(path.to_owned()),
+        };
+        let mut f = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        let len = f.seek(SeekFrom::End(0))?;
+
+        let complete = last_row_end(&mut f, len)?;
+        if complete != len {
+            match torn {
+                TornRowPolicy::Error => return Err(Error(ErrorKind::Deserialize(
+                    format!("File ends with a partial row of {} bytes", len - complete)
+                ))),
+                TornRowPolicy::Truncate => {
+                    f.set_len(complete)?;
+                    f.sync_all()?;
+                },
+            }
+        }
+
+        let columns = match complete {
+            0 => None,
+            _ => {
+                f.seek(SeekFrom::Start(0))?;
+                let mut first = Vec::new();
+                BufReader::new(&f).take(complete).read_until(ROW_TERM_BYTE, &mut first)?;
+                Some(first.iter().filter(|&&b| b == VALUE_TERM_BYTE).count())
+            },
+        };
+
+        Ok((FileSink { file: Encoder::new(f, Compression::None)?, atomic: None, created, finished: false }, columns))
+    }
+
+    pub fn get_ref(&self) -> &File {
+        self.file.get_ref()
+    }
+
+    /// Flushes the file and syncs it to disk, along with its directory entry
+    /// the first time after it was created.
+    pub(crate) fn sync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.file.get_ref().sync_data()?;
+
+        if let Some(path) = &self.created {
+            sync_parent(path)?;
+            self.created = None;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the file and, for atomic writes, syncs it and moves it into place.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.file.finish()?;
+            self.finished = true;
+        }
+
+        if let Some((tmp, target)) = &self.atomic {
+            self.file.get_ref().sync_all()?;
+            fs::rename(tmp, target)?;
+        }
+        if let Some((_, target)) = self.atomic.take() {
+            sync_parent(&target)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::other("File was already finished"));
+        }
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.finished {
+            true => Ok(()),
+            false => self.file.flush(),
+        }
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        // An unfinished atomic write must never become visible
+        match self.atomic.take() {
+            Some((tmp, _)) => { let _ = fs::remove_file(tmp); },
+            // Best effort, so a dropped writer still leaves a readable file
+            None => { let _ = self.finish(); },
+        }
+    }
+}
+
+/// Syncs the directory containing `path` so a new or renamed entry survives a crash.
+pub(crate) fn sync_parent(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+/// Returns the length of `f` up to and including its last `ROW_TERM_BYTE`.
+pub(crate) fn last_row_end(f: &mut File, len: u64) -> io::Result<u64> {
+    let mut chunk = [0; 4096];
+    let mut end = len;
+
+    while end > 0 {
+        let start = end.saturating_sub(chunk.len() as u64);
+        let chunk = &mut chunk[..(end - start) as usize];
+        f.seek(SeekFrom::Start(start))?;
+        f.read_exact(chunk)?;
+
+        if let Some(i) = chunk.iter().rposition(|&b| b == ROW_TERM_BYTE) {
+            return Ok(start + i as u64 + 1);
+        }
+        end = start;
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,40 @@
+use std::{fs::File, io::{self, Read, Seek, SeekFrom}, path::Path};
+
+use crate::compression::Compression;
+
+/// A file opened by [`crate::reader::Reader::from_path`], decompressed on the
+/// fly if its first bytes match an enabled [`Compression`] format.
+pub struct FileSource {
+    rdr: Box<dyn Read + Send>,
+    compression: Compression,
+}
+
+impl FileSource {
+    pub(crate) fn open(path: &Path) -> io::Result<FileSource> {
+        let mut f = File::open(path)?;
+        let mut head = Vec::with_capacity(10);
+        (&mut f).take(10).read_to_end(&mut head)?;
+        f.seek(SeekFrom::Start(0))?;
+
+        let compression = match Compression::detect(&head) {
+            // The bzip2 magic could as well be the start of a plain file
+            Compression::Bzip2 if Compression::from_extension(path) != Compression::Bzip2 => Compression::None,
+            detected => detected,
+        }.enabled();
+        Ok(FileSource {
+            rdr: compression.decoder(f)?,
+            compression,
+        })
+    }
+
+    /// The compression detected when the file was opened.
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+}
+
+impl Read for FileSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.rdr.read(buf)
+    }
+}
@@ -0,0 +1,147 @@
+//! Optional integrity trailer that lets a reader detect truncated or corrupted files.
+//!
+//! The trailer is an ordinary row with four values:
+//!
+//! 1. the marker [`TRAILER_MAGIC`]
+//! 2. the number of rows before the trailer, in decimal
+//! 3. the number of bytes before the trailer, in decimal
+//! 4. the CRC32C of those bytes, as 8 lowercase hex digits
+//!
+//! The counts and checksum cover everything since the start of the file or the
+//! previous trailer, so a file that was appended to holds one trailer per
+//! segment. Readers that don't verify trailers simply see one extra row.
+//!
+//! Trailer rows are exempt from the reader's [`crate::policy::Limits`]. Rows
+//! skipped for exceeding a limit can't be checked, so a segment holding any
+//! fails with [`ErrorKind::UnverifiedTrailer`] instead.
+//!
+//! See [`crate::writer::Writer::with_trailer`] and
+//! [`crate::reader::Reader::with_verify_trailer`].
+
+use crate::error::{Error, ErrorKind};
+use crate::utils::{ROW_TERM_BYTE, VALUE_TERM_BYTE};
+
+/// The first value of a trailer row.
+pub const TRAILER_MAGIC: &str = "RSV-TRAILER/1";
+
+// The marker, two u64 counts, the checksum and the terminators
+const MAX_TRAILER_LEN: usize = TRAILER_MAGIC.len() + 20 + 20 + 8 + 5;
+
+/// Running row count, byte length and checksum of the rows since the last trailer.
+#[derive(Clone, Debug)]
+pub(crate) struct Segment {
+    rows: u64,
+    bytes: u64,
+    crc: u32,
+    // Rows that exceeded a limit and were not added
+    skipped: u64,
+}
+
+impl Segment {
+    pub(crate) fn new() -> Segment {
+        Segment { rows: 0, bytes: 0, crc: !0, skipped: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows == 0 && self.bytes == 0 && self.skipped == 0
+    }
+
+    /// Counts a row that was skipped without being added.
+    pub(crate) fn skip(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// Adds a complete row, including its `ROW_TERM_BYTE`.
+    pub(crate) fn update(&mut self, row: &[u8]) {
+        self.rows += 1;
+        self.bytes += row.len() as u64;
+        for &b in row {
+            self.crc = CRC32C[((self.crc ^ b as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    /// Encodes the trailer row for the rows added so far.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut row = Vec::new();
+        for value in self.values() {
+            row.extend_from_slice(value.as_bytes());
+            row.push(VALUE_TERM_BYTE);
+        }
+        row.push(ROW_TERM_BYTE);
+        row
+    }
+
+    /// Checks a trailer row, with or without its `ROW_TERM_BYTE`, against the
+    /// rows added so far.
+    pub(crate) fn verify(&self, row: &[u8]) -> Result<(), Error> {
+        if self.skipped > 0 {
+            return Err(Error(ErrorKind::UnverifiedTrailer { skipped: self.skipped }));
+        }
+
+        let row = row.strip_suffix(&[ROW_TERM_BYTE]).unwrap_or(row);
+        let found: Vec<&[u8]> = row.split(|&b| b == VALUE_TERM_BYTE).collect();
+        let expected = self.values();
+
+        let names = ["row count", "byte length", "checksum"];
+        if found.len() != expected.len() + 1 || !found[found.len() - 1].is_empty() {
+            return Err(mismatch(format!("trailer has {} values, expected {}", found.len() - 1, expected.len())));
+        }
+        for ((name, found), expected) in names.iter().zip(&found[1..]).zip(&expected[1..]) {
+            if *found != expected.as_bytes() {
+                return Err(mismatch(format!(
+                    "{} is {}, trailer says {}", name, expected, String::from_utf8_lossy(found)
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn values(&self) -> [String; 4] {
+        [
+            TRAILER_MAGIC.to_owned(),
+            self.rows.to_string(),
+            self.bytes.to_string(),
+            format!("{:08x}", !self.crc),
+        ]
+    }
+}
+
+/// Returns `true` if `row` starts with the trailer marker.
+pub(crate) fn is_trailer(row: &[u8]) -> bool {
+    row.strip_prefix(TRAILER_MAGIC.as_bytes())
+        .is_some_and(|rest| rest.first() == Some(&VALUE_TERM_BYTE))
+}
+
+/// Returns `true` if `row`, which may be incomplete, could still be a trailer.
+pub(crate) fn may_be_trailer(row: &[u8]) -> bool {
+    let magic = TRAILER_MAGIC.as_bytes();
+    row.len() <= MAX_TRAILER_LEN && match row.len() > magic.len() {
+        true => is_trailer(row),
+        false => magic.starts_with(row),
+    }
+}
+
+fn mismatch(msg: String) -> Error {
+    Error(ErrorKind::Deserialize(format!("Integrity check failed: {}", msg)))
+}
+
+// Lookup table for the reflected CRC32C (Castagnoli) polynomial
+const CRC32C: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0x82F6_3B78,
+                _ => crc >> 1,
+            };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
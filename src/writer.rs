@@ -1,31 +1,99 @@
-use std::{fs::File, io::{BufWriter, Write}, path::Path};
+use std::{io::{self, BufWriter, Write}, path::Path, time::Instant};
 use crate::utils::{NULL_BYTE, ROW_TERM_BYTE, VALUE_TERM_BYTE};
 
 use serde::Serialize;
-use crate::serializer::SerRecord;
+use crate::serializer::{SerOptions, SerRecord};
+use crate::encoding::ByteEncoding;
+use crate::format::FloatFormat;
+use crate::policy::{Durability, TornRowPolicy};
+use crate::sink::FileSink;
+use crate::trailer::Segment;
 
-use crate::error::Error;
+use crate::error::{Error, ErrorKind, IntoInnerError};
 
 pub struct Writer<W> where W: Write {
-    wtr: W,
+    // Only `None` after `into_inner`, which needs to move out despite `Drop`
+    wtr: Option<W>,
+    // The row currently being encoded. It is only written to `wtr` once the
+    // whole row succeeded, so a failing field never leaves a partial row behind.
+    row: Vec<u8>,
+    fields: usize,
+    flexible: bool,
+    // Field count of the first row, used to check every later row
+    columns: Option<usize>,
+    rows: u64,
+    bytes: u64,
+    // Extra step run by `finish`, e.g. to move an atomic file into place
+    finish_fn: Option<fn(&mut W) -> io::Result<()>>,
+    // How to force `wtr` to disk, only set for file backed writers
+    sync_fn: Option<fn(&mut W) -> io::Result<()>>,
+    durability: Durability,
+    unsynced: u64,
+    last_sync: Instant,
+    // Only tracked when a trailer is written on finish
+    trailer: Option<Segment>,
+    pub(crate) opts: SerOptions,
 }
 
-impl Writer<BufWriter<File>> {
-    /// Creates a new buffered writer from a file path.
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<BufWriter<File>>, Error> {
-        let f = File::create(path)?;
+impl Writer<FileSink> {
+    /// Creates a new buffered writer from a file path. The file is compressed
+    /// based on its extension, see [`crate::compression`].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Writer<FileSink>, Error> {
+        let sink = FileSink::create(path.as_ref())?;
 
-        Ok(Writer::from_writer(f))
+        let mut wtr = Writer::from_sink(sink);
+        wtr.finish_fn = Some(FileSink::finish);
+        Ok(wtr)
+    }
+
+    /// Creates a writer that writes to a temporary file in the same directory
+    /// as `path`. The file is synced and renamed over `path` by [`Writer::finish`],
+    /// so readers only ever see the old or the complete new file.
+    ///
+    /// If the writer is dropped without finishing, the temporary file is removed.
+    pub fn create_atomic<P: AsRef<Path>>(path: P) -> Result<Writer<FileSink>, Error> {
+        let sink = FileSink::create_atomic(path.as_ref())?;
+
+        let mut wtr = Writer::from_sink(sink);
+        wtr.finish_fn = Some(FileSink::finish);
+        Ok(wtr)
+    }
+
+    /// Opens `path` for appending, creating it if it does not exist.
+    ///
+    /// The file must end with a complete row, otherwise `torn` decides whether
+    /// the partial row is truncated or an error is returned. New rows must have
+    /// as many fields as the first row of the file, which can be relaxed with
+    /// [`Writer::with_flexible`].
+    pub fn append_to_path<P: AsRef<Path>>(path: P, torn: TornRowPolicy) -> Result<Writer<FileSink>, Error> {
+        let (sink, columns) = FileSink::append(path.as_ref(), torn)?;
+
+        let mut wtr = Writer::from_sink(sink).with_flexible(false);
+        wtr.columns = columns;
+        wtr.finish_fn = Some(FileSink::finish);
+        Ok(wtr)
+    }
+
+    pub(crate) fn from_sink(sink: FileSink) -> Writer<FileSink> {
+        let mut wtr = Writer::from_writer_unbuffered(sink);
+        wtr.sync_fn = Some(FileSink::sync);
+        wtr
+    }
+
+    /// Sets how often written rows are synced to disk. See [`Durability`].
+    ///
+    /// A failed sync is returned by the write that triggered it. The row itself
+    /// has already been written at that point.
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self
     }
 }
 
 impl<W: Write> Writer<BufWriter<W>> {
     /// Creates a new buffered writer from a struct that implements the Write trait.
     pub fn from_writer(wtr: W) -> Writer<BufWriter<W>> {
-        let wtr = BufWriter::new(wtr);
-        Writer {
-            wtr,
-        }
+        Writer::from_writer_unbuffered(BufWriter::new(wtr))
     }
 }
 
@@ -33,29 +101,135 @@ impl<W: Write> Writer<W> {
     /// Creates a new *unbuffered* writer from a struct that implements the Write trait.
     pub fn from_writer_unbuffered(wtr: W) -> Self {
         Writer {
-            wtr,
+            wtr: Some(wtr),
+            row: Vec::new(),
+            fields: 0,
+            flexible: true,
+            columns: None,
+            rows: 0,
+            bytes: 0,
+            finish_fn: None,
+            sync_fn: None,
+            durability: Durability::Never,
+            unsynced: 0,
+            last_sync: Instant::now(),
+            trailer: None,
+            opts: SerOptions::default(),
         }
     }
 
+    /// Sets how byte fields are encoded. See [`ByteEncoding`].
+    pub fn with_byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.opts.bytes = encoding;
+        self
+    }
+
+    /// Sets how `f32` and `f64` values are formatted. See [`FloatFormat`].
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.opts.floats = format;
+        self
+    }
+
+    /// When disabled, every row must have as many fields as the first row or
+    /// writing it fails with [`ErrorKind::UnequalLengths`]. Enabled by default.
+    pub fn with_flexible(mut self, flexible: bool) -> Self {
+        self.flexible = flexible;
+        self
+    }
+
+    /// Makes [`Writer::finish`] append an integrity trailer with the row count,
+    /// byte length and checksum of the rows written. See [`crate::trailer`].
+    ///
+    /// When appending, the trailer only covers the rows added by this writer.
+    pub fn with_trailer(mut self, trailer: bool) -> Self {
+        self.trailer = trailer.then(Segment::new);
+        self
+    }
+
     pub fn flush(&mut self) -> Result<(), Error> {
-        Ok(self.wtr.flush()?)
+        Ok(self.wtr.as_mut().unwrap().flush()?)
+    }
+
+    /// Flushes the writer and syncs it to disk, including the directory entry
+    /// of a newly created file. Writers that are not backed by a file are only
+    /// flushed.
+    pub fn sync(&mut self) -> Result<(), Error> {
+        match self.sync_fn {
+            Some(sync) => sync(self.wtr.as_mut().unwrap())?,
+            None => self.flush()?,
+        }
+        self.unsynced = 0;
+        self.last_sync = Instant::now();
+        Ok(())
     }
 
     pub fn get_ref(&self) -> &W {
-        &self.wtr
+        self.wtr.as_ref().unwrap()
+    }
+
+    /// The number of rows written so far.
+    pub fn rows_written(&self) -> u64 {
+        self.rows
+    }
+
+    /// The number of bytes written so far, not counting anything that was
+    /// already in the file when appending.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Flushes the writer and returns the underlying writer.
+    ///
+    /// Unlike dropping the writer, a failed flush is reported. The error still
+    /// holds the writer so nothing is lost.
+    pub fn into_inner(mut self) -> Result<W, IntoInnerError<Writer<W>>> {
+        match self.flush() {
+            Ok(()) => Ok(self.wtr.take().unwrap()),
+            Err(e) => Err(IntoInnerError::new(self, e)),
+        }
     }
 
-    // pub fn into_inner(&self) -> Result<W, IntoInnerError<Writer<W>>> {
-    //     match self.flush() {
-    //         Ok(_) => Ok(self.wtr),
-    //         Err(e) => Err(IntoInnerError::new(self, e))
-    //     }
-    // }
+    /// Completes the document and returns the underlying writer.
+    ///
+    /// This is the preferred way to end a write, as any error while finishing
+    /// is returned instead of being discarded on drop.
+    pub fn finish(mut self) -> Result<W, IntoInnerError<Writer<W>>> {
+        if let Some(trailer) = self.trailer.take() {
+            let row = trailer.encode();
+            match self.wtr.as_mut().unwrap().write_all(&row) {
+                Ok(()) => {
+                    self.bytes += row.len() as u64;
+                    self.unsynced += 1;
+                },
+                Err(e) => {
+                    self.trailer = Some(trailer);
+                    return Err(IntoInnerError::new(self, e.into()));
+                },
+            }
+        }
+
+        if self.durability != Durability::Never && self.unsynced > 0 {
+            if let Err(e) = self.sync() {
+                return Err(IntoInnerError::new(self, e));
+            }
+        }
+
+        if let Some(finish) = self.finish_fn {
+            let res = self.flush().and_then(|_| Ok(finish(self.wtr.as_mut().unwrap())?));
+            if let Err(e) = res {
+                return Err(IntoInnerError::new(self, e));
+            }
+            self.finish_fn = None;
+        }
+
+        self.into_inner()
+    }
 
     /// Encode an iterable struct of items into a single record.
     pub fn write_record<'r, I, T>(&mut self, rec: I) -> Result<(), Error>
         where I: IntoIterator<Item = &'r Option<T>>, T: AsRef<[u8]> + 'r {
 
+        self.clear_row();
         for v in rec {
             match v {
                 Some(v) => self.write_value(v)?,
@@ -69,30 +243,95 @@ impl<W: Write> Writer<W> {
     }
 
     pub(crate) fn write_value<T: AsRef<[u8]>>(&mut self, value: T) -> Result<(), Error> {
-        self.wtr.write_all(value.as_ref())?;
-        self.wtr.write_all(&[VALUE_TERM_BYTE])?;
+        let value = value.as_ref();
+
+        // Reserved bytes inside a value would corrupt the framing of the stream
+        if let Some(b) = value.iter().find(|&&b| matches!(b, NULL_BYTE | ROW_TERM_BYTE | VALUE_TERM_BYTE)) {
+            return Err(Error(ErrorKind::Serialize(
+                format!("Value contains reserved byte {:#04X}", b)
+            )));
+        }
+
+        self.row.extend_from_slice(value);
+        self.row.push(VALUE_TERM_BYTE);
+        self.fields += 1;
 
         Ok(())
     }
 
     pub(crate) fn write_null(&mut self) -> Result<(), Error> {
-        self.write_value(&[NULL_BYTE])
+        self.row.extend_from_slice(&[NULL_BYTE, VALUE_TERM_BYTE]);
+        self.fields += 1;
+        Ok(())
+    }
+
+    fn clear_row(&mut self) {
+        self.row.clear();
+        self.fields = 0;
     }
 
+    /// Terminates the staged row and commits it to the underlying writer.
     pub(crate) fn write_row_term(&mut self) -> Result<(), Error> {
-        self.wtr.write_all(&[ROW_TERM_BYTE])?;
+        if !self.flexible {
+            match self.columns {
+                None => self.columns = Some(self.fields),
+                Some(expected) if expected != self.fields => {
+                    let err = ErrorKind::UnequalLengths { row: self.rows + 1, expected, actual: self.fields };
+                    self.clear_row();
+                    return Err(Error(err));
+                },
+                Some(_) => (),
+            }
+        }
+
+        self.row.push(ROW_TERM_BYTE);
+        if let Err(e) = self.wtr.as_mut().unwrap().write_all(&self.row) {
+            self.clear_row();
+            return Err(e.into());
+        }
+        if let Some(trailer) = &mut self.trailer {
+            trailer.update(&self.row);
+        }
+        self.rows += 1;
+        self.bytes += self.row.len() as u64;
+        self.unsynced += 1;
+        self.clear_row();
+
+        if self.durability.is_due(self.unsynced, self.last_sync.elapsed()) {
+            self.sync()?;
+        }
+
         Ok(())
     }
 
     pub fn serialize<S: Serialize>(&mut self, record: S) -> Result<(), Error> {
-        record.serialize(&mut SerRecord { wtr: self })?;
+        self.stage(record)?;
         self.write_row_term()?;
         Ok(())
     }
+
+    /// Encodes `record` into the staging buffer without committing it, and
+    /// returns the encoded values and their count.
+    pub(crate) fn stage<S: Serialize>(&mut self, record: S) -> Result<(&[u8], usize), Error> {
+        // Clearing first also drops anything left over by a field that panicked
+        self.clear_row();
+        record.serialize(&mut SerRecord { wtr: self })?;
+        Ok((&self.row, self.fields))
+    }
+
+    /// Commits a row encoded by [`Writer::stage`], possibly on another writer.
+    pub(crate) fn write_staged(&mut self, row: &[u8], fields: usize) -> Result<(), Error> {
+        self.clear_row();
+        self.row.extend_from_slice(row);
+        self.fields = fields;
+        self.write_row_term()
+    }
 }
 
 impl<W: Write> Drop for Writer<W> {
     fn drop(&mut self) {
-        let _ = self.flush();
+        if self.wtr.is_some() {
+            let _ = self.flush();
+        }
     }
 }
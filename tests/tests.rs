@@ -1,6 +1,8 @@
-use rsv_core::reader;
+use rsv_core::{reader, writer};
+use rsv_core::error::{Error, ErrorKind};
 use rsv_core::utils::{NULL_BYTE, VALUE_TERM_BYTE, ROW_TERM_BYTE};
-use serde::Deserialize;
+use rsv_core::{ByteEncoding, Durability, ExtraCells, FinalRowPolicy, Limit, Limits, MissingCells, RsvDocument, TornRowPolicy, FloatFormat, FloatNotation, NonFiniteSpelling, ParsePolicy, Truncation, Utf8Policy, Value, ValueInference};
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
@@ -9,6 +11,7 @@ pub enum NoValue {
 }
 
 #[test]
+#[allow(clippy::len_zero, clippy::comparison_to_empty)]
 fn seq_deserialization() {
     // empty buffer
     let buffer: Vec<u8> = vec![];
@@ -92,4 +95,1205 @@ fn seq_deserialization() {
         row_count += 1;
     }
     assert!(row_count == 2);
-}
\ No newline at end of file
+}
+
+#[test]
+fn value_deserialization() {
+    let buffer: Vec<u8> = vec![
+        b'a', VALUE_TERM_BYTE, b'4', b'2', VALUE_TERM_BYTE, NULL_BYTE, VALUE_TERM_BYTE,
+        b't', b'r', b'u', b'e', VALUE_TERM_BYTE, b'1', b'.', b'5', VALUE_TERM_BYTE, ROW_TERM_BYTE,
+        ROW_TERM_BYTE,
+    ];
+
+    // strings only
+    let rows: Vec<Vec<Value>> = reader::Reader::from_reader(&*buffer)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(rows.len() == 2);
+    assert!(rows[0] == vec![
+        Value::String("a".to_owned()),
+        Value::String("42".to_owned()),
+        Value::Null,
+        Value::String("true".to_owned()),
+        Value::String("1.5".to_owned()),
+    ]);
+    assert!(rows[1].is_empty());
+
+    // inferred scalars
+    let rows: Vec<Vec<Value>> = reader::Reader::from_reader(&*buffer)
+        .with_value_inference(ValueInference::Scalars)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(rows[0] == vec![
+        Value::String("a".to_owned()),
+        Value::Int(42),
+        Value::Null,
+        Value::Bool(true),
+        Value::Float(1.5),
+    ]);
+
+    // untagged enums
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Cell {
+        Num(i64),
+        Text(String),
+    }
+    let buffer: Vec<u8> = vec![b'7', VALUE_TERM_BYTE, b'x', VALUE_TERM_BYTE, ROW_TERM_BYTE];
+    let mut rdr = reader::Reader::from_reader(&*buffer).with_value_inference(ValueInference::Scalars);
+    let row = rdr.deserialize::<Vec<Cell>>().next().unwrap().unwrap();
+    assert!(row == vec![Cell::Num(7), Cell::Text("x".to_owned())]);
+
+    // only values that are written back unchanged are inferred
+    let cells = ["99999999999999999999", "inf", "NaN", "1e400", "-2.5e3", ".5", "1.", "+5", "007", "-0", "1.0", "-2.5", "-7"];
+    let mut wtr = writer::Writer::from_writer_unbuffered(vec![]);
+    wtr.serialize(cells).unwrap();
+    let buffer = wtr.into_inner().unwrap();
+    let mut rdr = reader::Reader::from_reader(&*buffer).with_value_inference(ValueInference::Scalars);
+    let row = rdr.deserialize::<Vec<Value>>().next().unwrap().unwrap();
+    assert!(row == vec![
+        Value::String("99999999999999999999".to_owned()),
+        Value::String("inf".to_owned()),
+        Value::String("NaN".to_owned()),
+        Value::String("1e400".to_owned()),
+        Value::String("-2.5e3".to_owned()),
+        Value::String(".5".to_owned()),
+        Value::String("1.".to_owned()),
+        Value::String("+5".to_owned()),
+        Value::String("007".to_owned()),
+        Value::String("-0".to_owned()),
+        Value::String("1.0".to_owned()),
+        Value::Float(-2.5),
+        Value::Int(-7),
+    ]);
+    let mut wtr = writer::Writer::from_writer_unbuffered(vec![]);
+    wtr.serialize(&row).unwrap();
+    assert!(wtr.into_inner().unwrap() == buffer);
+
+    // rows have no field names to flatten
+    #[derive(Deserialize, Debug)]
+    struct Flat {
+        _a: String,
+        #[serde(flatten)]
+        _rest: std::collections::HashMap<String, String>,
+    }
+    let buffer: Vec<u8> = vec![b'a', VALUE_TERM_BYTE, b'b', VALUE_TERM_BYTE, ROW_TERM_BYTE];
+    let err = reader::Reader::from_reader(&*buffer).deserialize::<Flat>().next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("flatten"));
+}
+
+#[test]
+fn bytes_round_trip() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Blob {
+        name: String,
+        #[serde(with = "serde_bytes")]
+        hash: Vec<u8>,
+    }
+
+    let record = Blob { name: "a".to_owned(), hash: vec![0xFF, 0xFE, 0xFD, 0x00, 0x7F] };
+
+    for encoding in [ByteEncoding::Hex, ByteEncoding::Base64] {
+        let mut buffer = Vec::new();
+        let mut wtr = writer::Writer::from_writer_unbuffered(&mut buffer).with_byte_encoding(encoding);
+        wtr.serialize(&record).unwrap();
+        drop(wtr);
+
+        assert!(!buffer[..buffer.len() - 1].contains(&ROW_TERM_BYTE));
+        let mut rdr = reader::Reader::from_reader(&*buffer).with_byte_encoding(encoding);
+        assert!(rdr.deserialize::<Blob>().next().unwrap().unwrap() == record);
+    }
+
+    let mut buffer = Vec::new();
+    let mut wtr = writer::Writer::from_writer_unbuffered(&mut buffer).with_byte_encoding(ByteEncoding::Reject);
+    assert!(wtr.serialize(&record).is_err());
+
+    // reserved bytes are refused on every write path
+    let mut wtr = writer::Writer::from_writer_unbuffered(Vec::new());
+    assert!(wtr.write_record(&[Some(vec![b'a', VALUE_TERM_BYTE])]).is_err());
+    assert!(wtr.serialize(["a\u{FF}"]).is_ok());
+    assert!(wtr.write_record(&[Some(vec![0xC3, 0xBF])]).is_ok());
+}
+
+#[test]
+fn wide_integers() {
+    let record = (i128::MIN, u128::MAX);
+
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(record).unwrap();
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<(i128, u128)>().next().unwrap().unwrap() == record);
+
+    // overflow is an error rather than a panic
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize((u128::MAX, -1i64)).unwrap();
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<(i128, u8)>().next().unwrap().is_err());
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<(u128, u8)>().next().unwrap().is_err());
+}
+
+#[test]
+fn parse_policy() {
+    #[derive(Deserialize, Debug)]
+    struct Messy {
+        flag: bool,
+        count: i64,
+        ratio: f64,
+        missing: Option<u32>,
+        note: Option<String>,
+    }
+
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer)
+        .serialize((" YES ", " 1,234,567 ", "-1.#INF", "", ""))
+        .unwrap();
+
+    // strict by default
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Messy>().next().unwrap().is_err());
+
+    let policy = ParsePolicy { thousands_separator: Some(','), ..ParsePolicy::lenient() };
+    let mut rdr = reader::Reader::from_reader(&*buffer).with_parse_policy(policy);
+    let row = rdr.deserialize::<Messy>().next().unwrap().unwrap();
+    assert!(row.flag);
+    assert!(row.count == 1_234_567);
+    assert!(row.ratio == f64::NEG_INFINITY);
+    assert!(row.missing.is_none());
+    assert!(row.note.is_none());
+}
+
+#[test]
+fn float_formatting() {
+    let values = [
+        0.1, -0.0, 1e300, 5e-324, f64::MAX, f64::MIN_POSITIVE, 1.0 / 3.0,
+        f64::INFINITY, f64::NEG_INFINITY, 123456789.125,
+    ];
+
+    for notation in [FloatNotation::Shortest, FloatNotation::Scientific] {
+        for non_finite in [NonFiniteSpelling::Short, NonFiniteSpelling::Long] {
+            let format = FloatFormat { notation, non_finite };
+            let mut buffer = Vec::new();
+            writer::Writer::from_writer_unbuffered(&mut buffer)
+                .with_float_format(format)
+                .serialize((values, f64::NAN, 0.1f32))
+                .unwrap();
+
+            let mut rdr = reader::Reader::from_reader(&*buffer);
+            let (row, nan, single) = rdr.deserialize::<([f64; 10], f64, f32)>().next().unwrap().unwrap();
+            for (a, b) in values.iter().zip(row.iter()) {
+                assert!(a.to_bits() == b.to_bits());
+            }
+            assert!(nan.is_nan());
+            assert!(single == 0.1f32);
+        }
+    }
+
+    let formatted = |notation, v: f64| {
+        let mut buffer = Vec::new();
+        writer::Writer::from_writer_unbuffered(&mut buffer)
+            .with_float_format(FloatFormat { notation, ..Default::default() })
+            .serialize([v])
+            .unwrap();
+        String::from_utf8(buffer[..buffer.len() - 2].to_vec()).unwrap()
+    };
+    assert!(formatted(FloatNotation::Fixed(2), 2.0 / 3.0) == "0.67");
+    assert!(formatted(FloatNotation::Significant(3), 1234.5) == "1.23e3");
+    assert!(formatted(FloatNotation::Significant(3), 9.996) == "10.0");
+    assert!(formatted(FloatNotation::Significant(3), 0.000123456) == "0.000123");
+    assert!(formatted(FloatNotation::Scientific, 1e6) == "1e6");
+
+    let format = FloatFormat { non_finite: NonFiniteSpelling::Reject, ..Default::default() };
+    let mut wtr = writer::Writer::from_writer_unbuffered(Vec::new()).with_float_format(format);
+    assert!(wtr.serialize([f64::NAN]).is_err());
+}
+
+#[test]
+fn serde_helpers() {
+    use rsv_core::serde_helpers::{bool_int, duration, ip};
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        #[serde(with = "bool_int")]
+        active: bool,
+        #[serde(with = "bool_int::option")]
+        verified: Option<bool>,
+        #[serde(with = "duration::millis")]
+        elapsed: Duration,
+        #[serde(with = "duration::nanos")]
+        precise: Duration,
+        #[serde(with = "ip")]
+        addr: IpAddr,
+    }
+
+    let row = Row {
+        active: true,
+        verified: None,
+        elapsed: Duration::from_millis(1500),
+        precise: Duration::new(3, 7),
+        addr: "::1".parse().unwrap(),
+    };
+
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(&row).unwrap();
+    assert!(buffer.starts_with(b"1\xFF\xFE\xFF1500\xFF3000000007\xFF::1\xFF"));
+
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Row>().next().unwrap().unwrap() == row);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_helpers() {
+    use chrono::{DateTime, NaiveDate, Utc};
+    use rsv_core::serde_helpers::chrono::{rfc3339, ts_milliseconds};
+
+    rsv_core::chrono_format!(mod us_date, chrono::NaiveDate, "%m/%d/%Y");
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        #[serde(with = "rfc3339")]
+        at: DateTime<Utc>,
+        #[serde(with = "ts_milliseconds::option")]
+        seen: Option<DateTime<Utc>>,
+        #[serde(with = "us_date")]
+        day: NaiveDate,
+    }
+
+    let at = DateTime::from_timestamp_millis(1_700_000_000_123).unwrap();
+    let row = Row { at, seen: Some(at), day: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap() };
+
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(&row).unwrap();
+    assert!(buffer.ends_with(b"\xFF1700000000123\xFF01/31/2024\xFF\xFD"));
+
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Row>().next().unwrap().unwrap() == row);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_helpers() {
+    use rsv_core::serde_helpers::time::{date, rfc3339, unix_timestamp, unix_timestamp_millis, unix_timestamp_nanos};
+    use time::{Date, Month, OffsetDateTime};
+
+    rsv_core::time_format!(mod us_date, time::Date, "[month]/[day]/[year]");
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        #[serde(with = "rfc3339")]
+        at: OffsetDateTime,
+        #[serde(with = "unix_timestamp")]
+        secs: OffsetDateTime,
+        #[serde(with = "unix_timestamp_millis::option")]
+        millis: Option<OffsetDateTime>,
+        #[serde(with = "unix_timestamp_nanos")]
+        nanos: OffsetDateTime,
+        #[serde(with = "date")]
+        day: Date,
+        #[serde(with = "us_date")]
+        us_day: Date,
+    }
+
+    let at = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_123_000_000).unwrap();
+    let secs = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+    let day = Date::from_calendar_date(2024, Month::January, 31).unwrap();
+    let row = Row { at, secs, millis: Some(at), nanos: at, day, us_day: day };
+
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(&row).unwrap();
+    assert!(buffer.ends_with(b"\xFF1700000000\xFF1700000000123\xFF1700000000123000000\xFF2024-01-31\xFF01/31/2024\xFF\xFD"));
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Row>().next().unwrap().unwrap() == row);
+
+    // out of range and malformed values fail instead of panicking
+    #[derive(Deserialize, Debug)]
+    struct Millis {
+        #[serde(with = "unix_timestamp_millis")]
+        _at: OffsetDateTime,
+    }
+    for cell in ["170141183460469231731687303715884105727", "99999999999999999999", "soon"] {
+        let mut buffer = cell.as_bytes().to_vec();
+        buffer.extend([VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+        assert!(reader::Reader::from_reader(&*buffer).deserialize::<Millis>().next().unwrap().is_err());
+    }
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid_helpers() {
+    use rsv_core::serde_helpers::uuid::{hyphenated, simple};
+    use uuid::Uuid;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        #[serde(with = "hyphenated")]
+        a: Uuid,
+        #[serde(with = "simple::option")]
+        b: Option<Uuid>,
+    }
+
+    let id = Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+    let row = Row { a: id, b: Some(id) };
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(&row).unwrap();
+    assert!(buffer == b"67e55044-10b1-426f-9247-bb680e5fe0c8\xFF67e5504410b1426f9247bb680e5fe0c8\xFF\xFD");
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Row>().next().unwrap().unwrap() == row);
+
+    let buffer = b"not-a-uuid\xFF\xFE\xFF\xFD";
+    assert!(reader::Reader::from_reader(&buffer[..]).deserialize::<Row>().next().unwrap().is_err());
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal_helpers() {
+    use rsv_core::serde_helpers::decimal::plain;
+    use rust_decimal::Decimal;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        #[serde(with = "plain")]
+        amount: Decimal,
+    }
+
+    let row = Row { amount: Decimal::new(1250, 2) };
+    let mut buffer = Vec::new();
+    writer::Writer::from_writer_unbuffered(&mut buffer).serialize(&row).unwrap();
+    assert!(buffer == b"12.50\xFF\xFD");
+    let mut rdr = reader::Reader::from_reader(&*buffer);
+    assert!(rdr.deserialize::<Row>().next().unwrap().unwrap() == row);
+
+    let read = |cell: &str| {
+        let mut buffer = cell.as_bytes().to_vec();
+        buffer.extend([VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+        reader::Reader::from_reader(&*buffer).deserialize::<Row>().next().unwrap()
+    };
+    assert!(read("1.25e1").unwrap() == Row { amount: Decimal::new(125, 1) });
+    assert!(read("12,50").is_err());
+    assert!(read("1e400").is_err());
+}
+
+#[test]
+fn document_functions() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Row {
+        id: u32,
+        name: Option<String>,
+    }
+
+    let rows = vec![
+        Row { id: 1, name: Some("a".to_owned()) },
+        Row { id: 2, name: None },
+    ];
+
+    let buffer = rsv_core::to_vec(&rows).unwrap();
+    assert!(rsv_core::from_slice::<Vec<Row>>(&buffer).unwrap() == rows);
+    assert!(rsv_core::from_reader::<_, Vec<Row>>(&*buffer).unwrap() == rows);
+    assert!(rsv_core::from_slice::<Vec<Row>>(&[]).unwrap().is_empty());
+
+    // a final row without its terminator is truncated
+    let truncated = |bytes: &[u8]| match rsv_core::from_slice::<Vec<Vec<String>>>(bytes) {
+        Err(Error(ErrorKind::Truncated { row, truncation })) => Some((row, truncation)),
+        _ => None,
+    };
+    assert!(truncated(b"a\xFF\xFDb\xFF") == Some((2, Truncation::MidRow)));
+    assert!(truncated(b"a\xFF\xFDb") == Some((2, Truncation::MidValue)));
+    assert!(rsv_core::from_reader::<_, Vec<Vec<String>>>(&b"a"[..]).is_err());
+
+    let mut written = Vec::new();
+    rsv_core::to_writer(&mut written, &rows).unwrap();
+    assert!(written == buffer);
+
+    let row: Row = rsv_core::from_record_bytes(&buffer[..buffer.iter().position(|&b| b == ROW_TERM_BYTE).unwrap()]).unwrap();
+    assert!(row == rows[0]);
+}
+
+#[test]
+fn reference_document_api() {
+    let rows = vec![
+        vec![Some("Hello".to_owned()), Some("🌎".to_owned()), None, Some(String::new())],
+        vec![],
+        vec![None],
+    ];
+
+    let encoded = rsv_core::encode_rsv(&rows);
+    assert!(rsv_core::is_valid_rsv(&encoded));
+    assert!(rsv_core::decode_rsv(&encoded).unwrap() == rows);
+    assert!(!rsv_core::is_valid_rsv(&encoded[..encoded.len() - 1]));
+    assert!(!rsv_core::is_valid_rsv(&[b'a', ROW_TERM_BYTE]));
+    assert!(!rsv_core::is_valid_rsv(&[NULL_BYTE, b'a', VALUE_TERM_BYTE, ROW_TERM_BYTE]));
+    assert!(rsv_core::is_valid_rsv(&[]));
+
+    let path = std::env::temp_dir().join(format!("rsv-reference-{}.rsv", std::process::id()));
+    let doc = RsvDocument::new(rows.clone());
+    doc.save(&path).unwrap();
+    assert!(RsvDocument::load(&path).unwrap() == doc);
+
+    rsv_core::append_rsv(&[vec![Some("x".to_owned())]], &path, false).unwrap();
+    rsv_core::append_rsv(&[vec![Some("y".to_owned())]], &path, true).unwrap();
+    let loaded = rsv_core::load_rsv(&path).unwrap();
+    assert!(loaded.len() == 4);
+    assert!(loaded[3] == vec![Some("x".to_owned()), Some("y".to_owned())]);
+
+    // a torn tail is refused and left untouched
+    std::fs::write(&path, [b'a', VALUE_TERM_BYTE, b'b']).unwrap();
+    assert!(rsv_core::append_rsv(&rows, &path, false).is_err());
+    assert!(std::fs::read(&path).unwrap().len() == 3);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_rows_are_not_written() {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        id: u32,
+        #[serde(with = "serde_bytes")]
+        blob: &'a [u8],
+    }
+
+    let mut buffer = Vec::new();
+    let mut wtr = writer::Writer::from_writer_unbuffered(&mut buffer).with_byte_encoding(ByteEncoding::Reject);
+    wtr.serialize(("a", 1)).unwrap();
+    assert!(wtr.serialize(Row { id: 2, blob: b"x" }).is_err());
+    assert!(wtr.write_record(&[Some("b"), Some("c\u{0}"), Some("\u{FF}")]).is_ok());
+    assert!(wtr.write_record(&[Some(&b"d"[..]), Some(&[ROW_TERM_BYTE][..])]).is_err());
+    wtr.serialize(("e", 3)).unwrap();
+    drop(wtr);
+
+    let rows = rsv_core::decode_rsv(&buffer).unwrap();
+    assert!(rows.len() == 3);
+    assert!(rows[0] == vec![Some("a".to_owned()), Some("1".to_owned())]);
+    assert!(rows[2] == vec![Some("e".to_owned()), Some("3".to_owned())]);
+}
+
+#[test]
+fn into_inner_reports_flush_errors() {
+    #[derive(Debug)]
+    struct FailingFlush(Vec<u8>);
+
+    impl std::io::Write for FailingFlush {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::ErrorKind::StorageFull.into())
+        }
+    }
+
+    let mut wtr = writer::Writer::from_writer(FailingFlush(Vec::new()));
+    wtr.serialize(["a"]).unwrap();
+    let err = wtr.into_inner().unwrap_err();
+    assert!(err.error().to_string().starts_with("io error"));
+    assert!(err.into_inner().get_ref().get_ref().0 == [b'a', VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+
+    let mut wtr = writer::Writer::from_writer(Vec::new());
+    wtr.serialize(["a"]).unwrap();
+    let buffer = wtr.finish().unwrap().into_inner().unwrap();
+    assert!(buffer == [b'a', VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+}
+
+#[test]
+fn strict_column_counts() {
+    let mut buffer = Vec::new();
+    let mut wtr = writer::Writer::from_writer_unbuffered(&mut buffer).with_flexible(false);
+    wtr.serialize(("a", 1)).unwrap();
+    match wtr.serialize(("b", 2, 3)) {
+        Err(Error(ErrorKind::UnequalLengths { row, expected, actual })) => {
+            assert!((row, expected, actual) == (2, 2, 3));
+        },
+        _ => panic!("expected UnequalLengths"),
+    }
+    wtr.write_record(&[Some("c"), None]).unwrap();
+    drop(wtr);
+    assert!(rsv_core::decode_rsv(&buffer).unwrap().len() == 2);
+
+    // ragged input is accepted by default
+    buffer.extend_from_slice(&[b'x', VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+    assert!(reader::Reader::from_reader(&*buffer).deserialize::<Vec<Option<String>>>().all(|r| r.is_ok()));
+
+    let mut rdr = reader::Reader::from_reader(&*buffer).with_flexible(false);
+    let results = rdr.deserialize::<Vec<Option<String>>>().collect::<Vec<_>>();
+    assert!(results[0].is_ok() && results[1].is_ok());
+    match &results[2] {
+        Err(e) => assert!(e.to_string() == "row 3 has 1 fields, expected 2"),
+        Ok(_) => panic!("expected UnequalLengths"),
+    }
+}
+
+#[test]
+fn atomic_writer() {
+    let dir = std::env::temp_dir().join(format!("rsv-atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.rsv");
+    std::fs::write(&path, rsv_core::encode_rsv(&[vec![Some("old".to_owned())]])).unwrap();
+
+    // dropping without finishing keeps the old file and removes the temp file
+    let mut wtr = writer::Writer::create_atomic(&path).unwrap();
+    wtr.serialize(["new"]).unwrap();
+    drop(wtr);
+    assert!(rsv_core::load_rsv(&path).unwrap()[0][0].as_deref() == Some("old"));
+    assert!(std::fs::read_dir(&dir).unwrap().count() == 1);
+
+    let mut wtr = writer::Writer::create_atomic(&path).unwrap();
+    wtr.serialize(["new"]).unwrap();
+    assert!(rsv_core::load_rsv(&path).unwrap()[0][0].as_deref() == Some("old"));
+    wtr.finish().unwrap();
+    assert!(rsv_core::load_rsv(&path).unwrap()[0][0].as_deref() == Some("new"));
+    assert!(std::fs::read_dir(&dir).unwrap().count() == 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn append_to_path() {
+    let path = std::env::temp_dir().join(format!("rsv-append-{}.rsv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut wtr = writer::Writer::append_to_path(&path, TornRowPolicy::Error).unwrap();
+    wtr.serialize(("id", "name")).unwrap();
+    wtr.finish().unwrap();
+
+    let mut wtr = writer::Writer::append_to_path(&path, TornRowPolicy::Error).unwrap();
+    wtr.serialize((1, "a")).unwrap();
+    assert!(wtr.serialize((2, "b", "extra")).is_err());
+    wtr.finish().unwrap();
+
+    // simulate a crash mid-row
+    let mut torn = std::fs::read(&path).unwrap();
+    torn.extend_from_slice(&[b'2', VALUE_TERM_BYTE, b'b']);
+    std::fs::write(&path, &torn).unwrap();
+
+    assert!(writer::Writer::append_to_path(&path, TornRowPolicy::Error).is_err());
+    assert!(std::fs::read(&path).unwrap() == torn);
+
+    let mut wtr = writer::Writer::append_to_path(&path, TornRowPolicy::Truncate).unwrap();
+    wtr.serialize((2, "b")).unwrap();
+    wtr.finish().unwrap();
+
+    let rows = rsv_core::load_rsv(&path).unwrap();
+    assert!(rows.len() == 3);
+    assert!(rows[2] == vec![Some("2".to_owned()), Some("b".to_owned())]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rotating_writer() {
+    use rsv_core::rotating::RotatingWriter;
+    use std::sync::{Arc, Mutex};
+
+    let dir = std::env::temp_dir().join(format!("rsv-rotating-{}", std::process::id()));
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let hook = completed.clone();
+
+    let mut wtr = RotatingWriter::new(dir.join("part-{index}.rsv").to_str().unwrap())
+        .with_max_rows(2)
+        .with_header(["id", "name"])
+        .with_flexible(false)
+        .on_file_complete(move |path| {
+            hook.lock().unwrap().push(path.to_owned());
+            Ok(())
+        });
+
+    for i in 0..5 {
+        wtr.serialize((i, "x")).unwrap();
+    }
+    assert!(wtr.serialize((5, "x", "extra")).is_err());
+    assert!(completed.lock().unwrap().len() == 2);
+    wtr.finish().unwrap();
+
+    let completed = completed.lock().unwrap();
+    assert!(completed.len() == 3);
+    assert!(completed[2] == dir.join("part-2.rsv"));
+    for (i, path) in completed.iter().enumerate() {
+        let rows = rsv_core::load_rsv(path).unwrap();
+        assert!(rows[0] == vec![Some("id".to_owned()), Some("name".to_owned())]);
+        assert!(rows.len() == if i == 2 { 2 } else { 3 });
+    }
+
+    // rotating on size
+    let mut wtr = RotatingWriter::new(dir.join("size-{index}.rsv").to_str().unwrap()).with_max_bytes(10);
+    for _ in 0..3 {
+        wtr.serialize(["0123456789"]).unwrap();
+    }
+    wtr.finish().unwrap();
+    assert!(rsv_core::load_rsv(dir.join("size-2.rsv")).unwrap().len() == 1);
+
+    // a template that repeats a path fails instead of replacing the earlier file
+    let mut wtr = RotatingWriter::new(dir.join("fixed.rsv").to_str().unwrap()).with_max_rows(1);
+    wtr.serialize(["a"]).unwrap();
+    assert!(wtr.serialize(["b"]).is_err());
+    wtr.finish().unwrap();
+    assert!(rsv_core::load_rsv(dir.join("fixed.rsv")).unwrap() == vec![vec![Some("a".to_owned())]]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn partitioned_writer() {
+    use rsv_core::partitioned::PartitionedWriter;
+
+    let dir = std::env::temp_dir().join(format!("rsv-partitioned-{}", std::process::id()));
+
+    // one file open at a time, so every change of key evicts and later appends
+    let mut wtr = PartitionedWriter::by_column(&dir, ["region", "amount"], "region").unwrap()
+        .with_max_open(1)
+        .with_flexible(false);
+    for (region, amount) in [("eu", 1), ("us", 2), ("eu", 3), ("a/b", 4)] {
+        wtr.serialize((region, amount)).unwrap();
+    }
+    wtr.write_record(&[None, Some("5")]).unwrap();
+    assert!(wtr.serialize(("eu", 6, "extra")).is_err());
+    wtr.finish().unwrap();
+
+    let eu = rsv_core::load_rsv(dir.join("region=eu/part-0.rsv")).unwrap();
+    assert!(eu.len() == 3);
+    assert!(eu[0] == vec![Some("region".to_owned()), Some("amount".to_owned())]);
+    assert!(eu[2] == vec![Some("eu".to_owned()), Some("3".to_owned())]);
+    assert!(rsv_core::load_rsv(dir.join("region=us/part-0.rsv")).unwrap().len() == 2);
+    assert!(dir.join("region=a%2Fb/part-0.rsv").exists());
+    assert!(dir.join("region=__null__/part-0.rsv").exists());
+
+    // unknown column
+    assert!(PartitionedWriter::by_column(&dir, ["a"], "b").is_err());
+
+    // key function
+    let mut wtr = PartitionedWriter::new(&dir, "len", |row: &[Option<&str>]| row.len());
+    wtr.serialize(("a", "b")).unwrap();
+    wtr.serialize(["a"]).unwrap();
+    wtr.finish().unwrap();
+    assert!(rsv_core::load_rsv(dir.join("len=2/part-0.rsv")).unwrap().len() == 1);
+
+    // keys from a key function are escaped too and can't leave the directory
+    let mut wtr = PartitionedWriter::new(&dir, "k", |row: &[Option<&str>]| row[0].unwrap_or("").to_owned());
+    wtr.serialize(["x/../../escaped"]).unwrap();
+    wtr.serialize([".."]).unwrap();
+    wtr.serialize([""]).unwrap();
+    wtr.finish().unwrap();
+    assert!(dir.join("k=x%2F..%2F..%2Fescaped/part-0.rsv").exists());
+    assert!(dir.join("k=%2E./part-0.rsv").exists());
+    assert!(dir.join("k=__empty__/part-0.rsv").exists());
+    assert!(!dir.parent().unwrap().join("escaped").exists());
+
+    // real keys never collide with the markers for null and empty keys
+    let mut wtr = PartitionedWriter::by_column(&dir, ["k", "n"], "k").unwrap().with_max_open(1);
+    for row in [[Some(""), Some("1")], [Some("__empty__"), Some("2")], [Some(""), Some("3")], [None, Some("4")], [Some("__null__"), Some("5")]] {
+        wtr.write_record(&row).unwrap();
+    }
+    wtr.finish().unwrap();
+    assert!(rsv_core::load_rsv(dir.join("k=__empty__/part-0.rsv")).unwrap().len() == 3);
+    assert!(rsv_core::load_rsv(dir.join("k=%5F_empty__/part-0.rsv")).unwrap().len() == 2);
+    assert!(rsv_core::load_rsv(dir.join("k=__null__/part-0.rsv")).unwrap().len() == 2);
+    assert!(rsv_core::load_rsv(dir.join("k=%5F_null__/part-0.rsv")).unwrap().len() == 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn durability() {
+    let path = std::env::temp_dir().join(format!("rsv-durability-{}.rsv", std::process::id()));
+    let on_disk = || std::fs::read(&path).unwrap().len();
+
+    // rows stay buffered until the second one triggers a sync
+    let mut wtr = writer::Writer::from_path(&path).unwrap().with_durability(Durability::EveryRows(2));
+    wtr.serialize(["a"]).unwrap();
+    assert!(on_disk() == 0);
+    wtr.serialize(["b"]).unwrap();
+    assert!(on_disk() == 6);
+    wtr.serialize(["c"]).unwrap();
+    assert!(on_disk() == 6);
+    wtr.sync().unwrap();
+    assert!(on_disk() == 9);
+    wtr.finish().unwrap();
+
+    let mut wtr = writer::Writer::append_to_path(&path, TornRowPolicy::Error).unwrap()
+        .with_durability(Durability::EveryRow);
+    wtr.serialize(["d"]).unwrap();
+    assert!(on_disk() == 12);
+    drop(wtr);
+
+    // a zero interval syncs on every row
+    let mut wtr = writer::Writer::from_path(&path).unwrap()
+        .with_durability(Durability::Interval(std::time::Duration::ZERO));
+    wtr.serialize(["a"]).unwrap();
+    assert!(on_disk() == 3);
+    drop(wtr);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn integrity_trailer() {
+    let mut wtr = writer::Writer::from_writer_unbuffered(Vec::new()).with_trailer(true);
+    wtr.serialize(("a", 1)).unwrap();
+    wtr.serialize(("b", 2)).unwrap();
+    let buf = wtr.finish().unwrap();
+
+    let rows = rsv_core::decode_rsv(&buf).unwrap();
+    assert!(rows.len() == 3);
+    assert!(rows[2] == vec![
+        Some("RSV-TRAILER/1".to_owned()), Some("2".to_owned()), Some("10".to_owned()), Some("0e3ec9f0".to_owned())
+    ]);
+
+    let read = |bytes: &[u8]| reader::Reader::from_reader(bytes).with_verify_trailer(true)
+        .deserialize::<(String, i32)>()
+        .collect::<Result<Vec<_>, Error>>();
+    assert!(read(&buf).unwrap().len() == 2);
+
+    // a second segment, as written when appending
+    let mut appended = buf.clone();
+    let mut wtr = writer::Writer::from_writer_unbuffered(&mut appended).with_trailer(true);
+    wtr.serialize(("c", 3)).unwrap();
+    wtr.finish().unwrap();
+    assert!(read(&appended).unwrap().len() == 3);
+
+    // truncated on a row boundary
+    assert!(read(&buf[..5]).is_err());
+    // bit flip
+    let mut flipped = buf.clone();
+    flipped[0] = b'c';
+    assert!(read(&flipped).is_err());
+    // no trailer at all
+    assert!(read(&[]).is_err());
+    // trailers are plain rows to readers that don't verify them
+    assert!(reader::Reader::from_reader(&*buf).deserialize::<Vec<String>>().count() == 3);
+
+    // limits don't apply to the trailer itself
+    let tight = Limits { max_value_bytes: Some(5), max_fields: Some(2), max_row_bytes: Some(10) };
+    let rows: Vec<(String, i32)> = reader::Reader::from_reader(&*buf)
+        .with_verify_trailer(true)
+        .with_limits(tight)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(rows.len() == 2);
+
+    // but rows skipped for a limit leave the trailer unverifiable rather than mismatched
+    let mut wtr = writer::Writer::from_writer_unbuffered(Vec::new()).with_trailer(true);
+    wtr.serialize(("a", 1)).unwrap();
+    wtr.serialize(("too long", 2)).unwrap();
+    let buf = wtr.finish().unwrap();
+    let mut rdr = reader::Reader::from_reader(&*buf)
+        .with_verify_trailer(true)
+        .with_limits(tight)
+        .with_error_recovery(true);
+    let rows: Vec<_> = rdr.deserialize::<(String, i32)>().collect();
+    assert!(rows.len() == 2 && rows[0].is_ok());
+    assert!(matches!(rows[1], Err(Error(ErrorKind::UnverifiedTrailer { skipped: 1 }))));
+}
+
+#[test]
+fn follow_reader() {
+    use rsv_core::follow::FollowReader;
+    use std::io::Write;
+
+    let path = std::env::temp_dir().join(format!("rsv-follow-{}.rsv", std::process::id()));
+    let append = |bytes: &[u8]| std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(bytes).unwrap();
+    std::fs::write(&path, b"a\xFF\xFDb").unwrap();
+
+    let mut rdr = FollowReader::from_path(&path).unwrap().with_poll_interval(std::time::Duration::from_millis(5));
+    let mut buf = Vec::new();
+    assert!(rdr.try_read_record(&mut buf).unwrap());
+    assert!(buf == b"a\xFF\xFD");
+
+    // waits for the rest of a partial row
+    buf.clear();
+    assert!(!rdr.try_read_record(&mut buf).unwrap());
+    append(b"c\xFF\xFD");
+    assert!(rdr.try_read_record(&mut buf).unwrap());
+    assert!(buf == b"bc\xFF\xFD");
+
+    // truncation starts over
+    std::fs::write(&path, b"d\xFF\xFD").unwrap();
+    assert!(rdr.deserialize::<Vec<String>>().next().unwrap().unwrap() == vec!["d"]);
+
+    // rotation finishes the old file first
+    append(b"e\xFF\xFD");
+    let rotated = path.with_extension("old");
+    std::fs::rename(&path, &rotated).unwrap();
+    std::fs::write(&path, b"f\xFF\xFD").unwrap();
+    let mut rows = rdr.deserialize::<Vec<String>>();
+    assert!(rows.next().unwrap().unwrap() == vec!["e"]);
+    assert!(rows.next().unwrap().unwrap() == vec!["f"]);
+
+    // including rows appended after its end was reached
+    buf.clear();
+    assert!(!rdr.try_read_record(&mut buf).unwrap());
+    append(b"f2\xFF\xFD");
+    std::fs::rename(&path, &rotated).unwrap();
+    std::fs::write(&path, b"f3\xFF\xFD").unwrap();
+    let mut rows = rdr.deserialize::<Vec<String>>();
+    assert!(rows.next().unwrap().unwrap() == vec!["f2"]);
+    assert!(rows.next().unwrap().unwrap() == vec!["f3"]);
+
+    // blocks until another thread writes
+    let writer = std::thread::spawn({
+        let path = path.clone();
+        move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            std::fs::OpenOptions::new().append(true).open(path).unwrap().write_all(b"g\xFF\xFD").unwrap();
+        }
+    });
+    assert!(rows.next().unwrap().unwrap() == vec!["g"]);
+    writer.join().unwrap();
+    assert!(rdr.position() == 8);
+
+    let mut rdr = FollowReader::from_path(&path).unwrap().from_end().unwrap()
+        .with_poll_interval(std::time::Duration::from_millis(5))
+        .with_reader(|rdr| rdr
+            .with_limits(Limits { max_row_bytes: Some(4), ..Limits::default() })
+            .with_error_recovery(true));
+    assert!(!rdr.try_read_record(&mut buf).unwrap());
+
+    // rows are read with the configured reader
+    append(b"h\xFF\xFDtoo long\xFF\xFDi\xFF\xFD");
+    let mut rows = rdr.deserialize::<Vec<String>>();
+    assert!(rows.next().unwrap().unwrap() == vec!["h"]);
+    assert!(rows.next().unwrap().unwrap() == vec!["i"]);
+    assert!(rdr.reader().diagnostics().len() == 1);
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&rotated).unwrap();
+}
+
+#[test]
+fn multi_reader() {
+    use rsv_core::multi::MultiReader;
+
+    let dir = std::env::temp_dir().join(format!("rsv-multi-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("2024-01-02")).unwrap();
+    std::fs::create_dir_all(dir.join("2024-01-01")).unwrap();
+    std::fs::write(dir.join("2024-01-01/part-0.rsv"), b"id\xFF\xFD1\xFF\xFD2\xFF\xFD").unwrap();
+    std::fs::write(dir.join("2024-01-02/part-0.rsv"), b"id\xFF\xFD3\xFF\xFDx\xFF\xFD").unwrap();
+    std::fs::write(dir.join("2024-01-02/notes.txt"), b"").unwrap();
+
+    let pattern = dir.join("2024-*/part-?.rsv");
+    let mut rdr = MultiReader::from_glob(pattern.to_str().unwrap()).unwrap().with_header(true);
+    let rows: Vec<_> = rdr.deserialize::<(u32,)>().collect();
+    assert!(rdr.header() == Some(&b"id\xFF\xFD"[..]));
+    assert!(rows.len() == 4);
+
+    let (source, record) = rows[2].as_ref().unwrap();
+    assert!(record.0 == 3);
+    assert!(*source.path == dir.join("2024-01-02/part-0.rsv"));
+    assert!(source.row == 2);
+    match &rows[3] {
+        Err(Error(ErrorKind::InFile { path, row: 3, .. })) => assert!(path.ends_with("2024-01-02/part-0.rsv")),
+        _ => panic!("expected an error in the second file"),
+    }
+
+    // mismatched header and missing file
+    std::fs::write(dir.join("other.rsv"), b"name\xFF\xFD").unwrap();
+    let mut rdr = MultiReader::from_paths([
+        dir.join("2024-01-01/part-0.rsv"), dir.join("other.rsv"), dir.join("missing.rsv")
+    ]).with_header(true);
+    let rows: Vec<_> = rdr.deserialize::<(u32,)>().collect();
+    assert!(rows.len() == 4);
+    assert!(matches!(&rows[2], Err(Error(ErrorKind::InFile { row: 1, .. }))));
+    assert!(matches!(&rows[3], Err(Error(ErrorKind::InFile { row: 0, .. }))));
+
+    // every reader setting applies to each file
+    std::fs::write(dir.join("long.rsv"), b"1\xFF\xFD22222\xFF\xFD3\xFF").unwrap();
+    std::fs::write(dir.join("bad.rsv"), b"4\xC3\xFF\xFD5\xFF").unwrap();
+    let mut rdr = MultiReader::from_paths([dir.join("long.rsv"), dir.join("bad.rsv")])
+        .with_reader(|rdr| rdr
+            .with_utf8_policy(Utf8Policy::Replace)
+            .with_limits(Limits { max_value_bytes: Some(3), ..Limits::default() })
+            .with_final_row_policy(FinalRowPolicy::Accept)
+            .with_error_recovery(true));
+    let rows: Vec<(String,)> = rdr.deserialize().map(|r| r.map(|(_, row)| row)).collect::<Result<_, _>>().unwrap();
+    assert!(rows == vec![("1".to_owned(),), ("3".to_owned(),), ("4\u{FFFD}".to_owned(),), ("5".to_owned(),)]);
+    let diagnostics = rdr.diagnostics();
+    assert!(diagnostics.len() == 2);
+    assert!(diagnostics[0].0.ends_with("long.rsv") && diagnostics[0].1.row == 2);
+    assert!(diagnostics[1].0.ends_with("bad.rsv") && diagnostics[1].1.row == 1);
+
+    // a pattern without matches is an error
+    let pattern = dir.join("2023-*/part-?.rsv");
+    assert!(matches!(MultiReader::from_glob(pattern.to_str().unwrap()), Err(Error(ErrorKind::IO(_)))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn compression() {
+    use rsv_core::compression::Compression;
+
+    assert!(Compression::detect(b"a\xFF\xFD") == Compression::None);
+    assert!(Compression::detect(b"BZh91AY&SY") == Compression::Bzip2);
+    assert!(Compression::detect(b"BZh9 text\xFF") == Compression::None);
+    assert!(Compression::from_extension(std::path::Path::new("a.rsv.gz")) == Compression::Gzip);
+
+    // a plain file that happens to start with the bzip2 magic
+    let path = std::env::temp_dir().join(format!("rsv-compression-{}.rsv", std::process::id()));
+    std::fs::write(&path, b"BZh91AY&SY\xFF\xFD").unwrap();
+    let mut rdr = reader::Reader::from_path(&path).unwrap();
+    assert!(rdr.deserialize::<(String,)>().next().unwrap().unwrap().0 == "BZh91AY&SY");
+    std::fs::remove_file(&path).unwrap();
+
+    let formats = [
+        ("zst", Compression::Zstd, cfg!(feature = "zstd")),
+        ("gz", Compression::Gzip, cfg!(feature = "gzip")),
+        ("xz", Compression::Xz, cfg!(feature = "xz")),
+        ("bz2", Compression::Bzip2, cfg!(feature = "bzip2")),
+    ];
+    for (ext, compression, enabled) in formats {
+        let path = std::env::temp_dir().join(format!("rsv-compression-{}.rsv.{}", std::process::id(), ext));
+        if !enabled {
+            // written and read as a plain file
+            let mut wtr = writer::Writer::from_path(&path).unwrap();
+            wtr.serialize(("a", 1)).unwrap();
+            wtr.finish().unwrap();
+            assert!(std::fs::read(&path).unwrap() == b"a\xFF1\xFF\xFD");
+            assert!(reader::Reader::from_path(&path).unwrap().deserialize::<(String, i32)>().next().unwrap().is_ok());
+            std::fs::remove_file(&path).unwrap();
+            continue;
+        }
+
+        let mut wtr = writer::Writer::from_path(&path).unwrap();
+        wtr.serialize(("a", 1)).unwrap();
+        wtr.finish().unwrap();
+        assert!(Compression::detect(&std::fs::read(&path).unwrap()) == compression);
+
+        let mut rdr = reader::Reader::from_path(&path).unwrap();
+        let rows: Vec<(String, i32)> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+        assert!(rows == vec![("a".to_owned(), 1)]);
+
+        // dropping the writer still ends the stream, and atomic writes compress too
+        for atomic in [false, true] {
+            let mut wtr = match atomic {
+                false => writer::Writer::from_path(&path).unwrap(),
+                true => writer::Writer::create_atomic(&path).unwrap(),
+            };
+            wtr.serialize(("b", 2)).unwrap();
+            match atomic {
+                false => drop(wtr),
+                true => { wtr.finish().unwrap(); },
+            }
+            let mut rdr = reader::Reader::from_path(&path).unwrap();
+            assert!(rdr.deserialize::<(String, i32)>().next().unwrap().unwrap().1 == 2);
+        }
+
+        assert!(writer::Writer::append_to_path(&path, TornRowPolicy::Error).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn row_length_policies() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V2 {
+        id: u32,
+        name: Option<String>,
+        score: f64,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct CatchAll {
+        id: u32,
+        rest: Vec<Option<String>>,
+    }
+
+    let short = b"1\xFF\xFD";
+    let long = b"1\xFFa\xFF2.5\xFFx\xFF\xFE\xFF\xFD";
+    let read = |bytes: &'static [u8], missing, extra| reader::Reader::from_reader(bytes)
+        .with_missing_cells(missing)
+        .with_extra_cells(extra)
+        .deserialize::<V2>()
+        .next()
+        .unwrap();
+
+    let err = read(short, MissingCells::Error, ExtraCells::Ignore).unwrap_err();
+    assert!(err.to_string() == "row 1 has 1 fields, expected 3");
+    assert!(read(short, MissingCells::Null, ExtraCells::Ignore).is_err());
+    assert!(read(short, MissingCells::Default, ExtraCells::Ignore).unwrap() == V2 { id: 1, name: None, score: 0.0 });
+
+    assert!(read(long, MissingCells::Error, ExtraCells::Ignore).unwrap() == V2 { id: 1, name: Some("a".to_owned()), score: 2.5 });
+    let err = read(long, MissingCells::Error, ExtraCells::Reject).unwrap_err();
+    assert!(err.to_string() == "row 1 has 5 fields, expected 3");
+
+    let read = |bytes: &'static [u8]| reader::Reader::from_reader(bytes)
+        .with_extra_cells(ExtraCells::Collect)
+        .deserialize::<CatchAll>()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!(read(long).rest == vec![Some("a".to_owned()), Some("2.5".to_owned()), Some("x".to_owned()), None]);
+    assert!(read(short).rest.is_empty());
+
+    // tuples follow the same rules
+    let row: (u32, Option<String>) = reader::Reader::from_reader(&short[..])
+        .with_missing_cells(MissingCells::Null)
+        .deserialize()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert!(row == (1, None));
+
+    // extra values are counted as cells when a field is a nested struct
+    #[derive(Deserialize, Debug)]
+    struct Point {
+        _x: u32,
+        _y: u32,
+    }
+    let err = reader::Reader::from_reader(&b"1\xFF2\xFF3\xFF4\xFF\xFD"[..])
+        .with_extra_cells(ExtraCells::Reject)
+        .deserialize::<(Point, u32)>()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(err.to_string() == "row 1 has 4 fields, expected 3");
+
+    // and are reported as such when recovering
+    let mut rdr = reader::Reader::from_reader(&b"1\xFF\xFD1\xFFa\xFF2.5\xFF\xFD"[..]).with_error_recovery(true);
+    let rows: Vec<V2> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+    assert!(rows.len() == 1);
+    assert!(rdr.diagnostics()[0].kind == rsv_core::diagnostics::DiagnosticKind::UnequalLengths { expected: 3, actual: 1 });
+}
+
+#[test]
+fn utf8_salvage() {
+    use rsv_core::diagnostics::DiagnosticKind;
+
+    // an invalid sequence in the first value and a stray null byte in the second
+    let buf = b"a\xC3(\xFF\xFEb\xFF\xFDok\xFFok\xFF\xFD";
+    let read = |utf8| {
+        let mut rdr = reader::Reader::from_reader(&buf[..]).with_utf8_policy(utf8);
+        let rows: Vec<_> = rdr.deserialize::<(Option<String>, Option<String>)>().collect();
+        (rows, rdr.diagnostics().to_vec())
+    };
+
+    let (rows, diagnostics) = read(Utf8Policy::Strict);
+    assert!(rows[0].is_err() && rows[1].is_ok());
+    assert!(diagnostics.is_empty());
+
+    let (rows, diagnostics) = read(Utf8Policy::Replace);
+    let row = rows[0].as_ref().unwrap();
+    assert!(row.0.as_deref() == Some("a\u{FFFD}(") && row.1.as_deref() == Some("\u{FFFD}b"));
+    assert!(diagnostics.len() == 2);
+    assert!(diagnostics[0].row == 1 && diagnostics[0].field == Some(0) && diagnostics[0].kind == DiagnosticKind::InvalidUtf8);
+    assert!(diagnostics[1].field == Some(1) && diagnostics[1].byte_offset == 4 && diagnostics[1].kind == DiagnosticKind::StrayNullByte);
+    assert!(diagnostics[1].raw_excerpt == b"\xFEb");
+
+    let (rows, diagnostics) = read(Utf8Policy::Skip);
+    assert!(*rows[0].as_ref().unwrap() == (None, None));
+    assert!(diagnostics.len() == 2);
+
+    // raw bytes only fit byte targets
+    let (rows, _) = read(Utf8Policy::RawBytes);
+    assert!(rows[0].is_err());
+    let mut rdr = reader::Reader::from_reader(&buf[..]).with_utf8_policy(Utf8Policy::RawBytes);
+    let row = rdr.deserialize::<(serde_bytes::ByteBuf, serde_bytes::ByteBuf)>().next().unwrap().unwrap();
+    assert!(row.0.as_slice() == b"a\xC3(" && row.1.as_slice() == b"\xFEb");
+    assert!(rdr.diagnostics().len() == 2);
+
+    // and dynamic values
+    let mut rdr = reader::Reader::from_reader(&buf[..]).with_utf8_policy(Utf8Policy::RawBytes);
+    let rows: Vec<Vec<Value>> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+    assert!(rows[0] == vec![Value::Bytes(b"a\xC3(".to_vec()), Value::Bytes(b"\xFEb".to_vec())]);
+    assert!(rows[1] == vec![Value::String("ok".to_owned()), Value::String("ok".to_owned())]);
+}
+
+#[test]
+fn resource_limits() {
+    let read = |bytes: &[u8], limits| {
+        let mut rdr = reader::Reader::from_reader(bytes).with_limits(limits);
+        let rows: Vec<_> = rdr.deserialize::<Vec<String>>().collect();
+        rows
+    };
+    let limit = |res: &Result<Vec<String>, Error>| match res {
+        Err(Error(ErrorKind::LimitExceeded { row, limit, .. })) => Some((*row, *limit)),
+        _ => None,
+    };
+
+    let buf = b"ab\xFFc\xFF\xFDabcdef\xFF\xFDa\xFF\xFD";
+    let rows = read(buf, Limits { max_value_bytes: Some(3), ..Limits::default() });
+    assert!(rows.len() == 3);
+    assert!(limit(&rows[1]) == Some((2, Limit::ValueBytes)));
+    assert!(rows[2].as_ref().unwrap() == &vec!["a".to_owned()]);
+
+    let rows = read(buf, Limits { max_fields: Some(1), ..Limits::default() });
+    assert!(limit(&rows[0]) == Some((1, Limit::Fields)));
+    assert!(rows[1].is_ok() && rows[2].is_ok());
+
+    // a file without any terminators is not buffered past the limit
+    let huge = vec![b'x'; 100_000];
+    let rows = read(&huge, Limits { max_row_bytes: Some(1024), ..Limits::default() });
+    assert!(rows.len() == 1);
+    assert!(limit(&rows[0]) == Some((1, Limit::RowBytes)));
+    assert!(rows[0].as_ref().unwrap_err().to_string() == "row 1 has more than 1024 bytes");
+}
+
+#[test]
+fn error_recovery() {
+    use rsv_core::diagnostics::{DiagnosticKind, Summary};
+
+    let mut buf = Vec::new();
+    for row in ["1", "x", "3", "0123456789", "5"] {
+        buf.extend_from_slice(row.as_bytes());
+        buf.extend_from_slice(&[VALUE_TERM_BYTE, ROW_TERM_BYTE]);
+    }
+
+    let mut rdr = reader::Reader::from_reader(&*buf)
+        .with_error_recovery(true)
+        .with_limits(Limits { max_value_bytes: Some(5), ..Limits::default() });
+    let rows: Vec<(u32,)> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+    assert!(rows == vec![(1,), (3,), (5,)]);
+
+    let diagnostics = rdr.diagnostics();
+    assert!(diagnostics.len() == 2);
+    assert!(diagnostics[0].row == 2 && diagnostics[0].byte_offset == 3 && diagnostics[0].field == Some(0));
+    assert!(matches!(&diagnostics[0].kind, DiagnosticKind::Malformed(msg) if msg.contains("\"x\"")));
+    assert!(diagnostics[0].raw_excerpt == b"x\xFF\xFD");
+    assert!(diagnostics[1].row == 4 && diagnostics[1].kind == DiagnosticKind::LimitExceeded(Limit::ValueBytes));
+    assert!(diagnostics[1].raw_excerpt.starts_with(b"012345"));
+    assert!(rdr.summary() == Summary { rows_read: 5, rows_skipped: 2, diagnostics: 2, diagnostics_dropped: 0 });
+
+    // the list is bounded but everything is counted
+    let mut rdr = reader::Reader::from_reader(&*buf).with_error_recovery(true).with_max_diagnostics(0);
+    assert!(rdr.deserialize::<(u8,)>().count() == 3);
+    assert!(rdr.diagnostics().is_empty());
+    assert!(rdr.summary().diagnostics_dropped == 2);
+
+    // without recovery the error is returned as before
+    let mut rdr = reader::Reader::from_reader(&*buf);
+    assert!(rdr.deserialize::<(u8,)>().filter(Result::is_err).count() == 2);
+}
+
+#[test]
+fn final_row_policy() {
+    let mid_value = b"a\xFF\xFDb\xFFc";
+    let mid_row = b"a\xFF\xFDb\xFF";
+
+    let read = |bytes: &'static [u8], policy| {
+        let mut rdr = reader::Reader::from_reader(bytes).with_final_row_policy(policy);
+        let rows: Vec<_> = rdr.deserialize::<Vec<String>>().collect();
+        (rows, rdr.truncation())
+    };
+
+    let (rows, truncation) = read(mid_value, FinalRowPolicy::Error);
+    assert!(rows.len() == 2 && truncation == Some(Truncation::MidValue));
+    assert!(matches!(rows[1], Err(Error(ErrorKind::Truncated { row: 2, truncation: Truncation::MidValue }))));
+    let (rows, truncation) = read(mid_row, FinalRowPolicy::Error);
+    assert!(truncation == Some(Truncation::MidRow));
+    assert!(rows[1].as_ref().unwrap_err().to_string() == "input ends before the terminator of row 2");
+
+    let (rows, _) = read(mid_value, FinalRowPolicy::Accept);
+    assert!(rows[1].as_ref().unwrap() == &vec!["b".to_owned(), "c".to_owned()]);
+    let (rows, _) = read(mid_row, FinalRowPolicy::Accept);
+    assert!(rows[1].as_ref().unwrap() == &vec!["b".to_owned()]);
+
+    let (rows, truncation) = read(mid_value, FinalRowPolicy::Ignore);
+    assert!(rows.len() == 1 && truncation == Some(Truncation::MidValue));
+
+    // complete input is unaffected
+    let (rows, truncation) = read(b"a\xFF\xFD", FinalRowPolicy::Error);
+    assert!(rows.len() == 1 && rows[0].is_ok() && truncation.is_none());
+}